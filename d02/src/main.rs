@@ -1,10 +1,12 @@
+use std::env;
+use std::fs;
 use std::io;
 
-fn parse_line(l: &str) -> u8 {
-    let l = l.chars().collect::<Vec<_>>();
-    let (op, x) = (l[0] as u8 - 'A' as u8, l[2] as u8 - 'X' as u8);
-    let my = calc_move(op, x);
-    calc_outcome(op, my) + 1 + my
+const SHAPES: [&str; 3] = ["rock", "paper", "scissors"];
+
+fn parse_line(l: &str) -> (u8, u8) {
+    let l = l.as_bytes();
+    (l[0] - b'A', l[2] - b'X')
 }
 
 fn calc_move(op: u8, x: u8) -> u8 {
@@ -15,12 +17,286 @@ fn calc_outcome(op: u8, my: u8) -> u8 {
     (my + 4 - op) % 3 * 3
 }
 
+fn score(op: u8, my: u8) -> u8 {
+    calc_outcome(op, my) + 1 + my
+}
+
+/// Total score when every column value `x` is played as the shape `mapping[x]`.
+fn mapped_total(rounds: &[(u8, u8)], mapping: &[u8; 3]) -> i32 {
+    rounds
+        .iter()
+        .map(|&(op, x)| score(op, mapping[x as usize]) as i32)
+        .sum()
+}
+
+fn permutations() -> Vec<[u8; 3]> {
+    let mut r = Vec::new();
+    for a in 0..3 {
+        for b in 0..3 {
+            for c in 0..3 {
+                if a != b && b != c && a != c {
+                    r.push([a, b, c]);
+                }
+            }
+        }
+    }
+    r
+}
+
+fn optimize(rounds: &[(u8, u8)]) {
+    let mut freq = [0; 3];
+    for &(op, _) in rounds {
+        freq[op as usize] += 1;
+    }
+    let n = rounds.len() as f64;
+    println!("opponent distribution:");
+    for (op, f) in freq.iter().enumerate() {
+        println!("  {:>8}: {:.3}", SHAPES[op], *f as f64 / n);
+    }
+
+    // Expected score is linear in the response probabilities, so the best
+    // response to a fixed distribution is always a pure shape.
+    let expected = (0..3)
+        .map(|my| {
+            (0..3)
+                .map(|op| freq[op as usize] as f64 / n * score(op, my) as f64)
+                .sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+    let mut best = 0;
+    println!("expected score per round:");
+    for (my, e) in expected.iter().enumerate() {
+        println!("  {:>8}: {:.3}", SHAPES[my], e);
+        if *e > expected[best] {
+            best = my;
+        }
+    }
+    println!(
+        "best response: always {} ({:.3} per round, {:.0} total)",
+        SHAPES[best],
+        expected[best],
+        expected[best] * n
+    );
+
+    let guide: i32 = rounds
+        .iter()
+        .map(|&(op, x)| score(op, calc_move(op, x)) as i32)
+        .sum();
+    let (mapping, total) = permutations()
+        .into_iter()
+        .map(|m| (m, mapped_total(rounds, &m)))
+        .max_by_key(|(_, t)| *t)
+        .unwrap();
+    println!(
+        "best fixed mapping: X={} Y={} Z={} total: {}",
+        SHAPES[mapping[0] as usize],
+        SHAPES[mapping[1] as usize],
+        SHAPES[mapping[2] as usize],
+        total
+    );
+    println!("guide total: {} improvement: {}", guide, total - guide);
+}
+
+/// Small xorshift generator so tournaments are reproducible from a seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+enum Selection {
+    Sequential,
+    Random(Rng),
+}
+
+struct Player {
+    name: String,
+    guide: Vec<(u8, u8)>,
+}
+
+impl Player {
+    /// The shape the guide tells this player to throw in round `i`.
+    fn shape(&self, i: usize, selection: &mut Selection) -> u8 {
+        let idx = match selection {
+            Selection::Sequential => i % self.guide.len(),
+            Selection::Random(rng) => rng.next() as usize % self.guide.len(),
+        };
+        let (op, x) = self.guide[idx];
+        calc_move(op, x)
+    }
+}
+
+#[derive(Default)]
+struct Standing {
+    played: u32,
+    won: u32,
+    drawn: u32,
+    lost: u32,
+    score_for: i64,
+    score_against: i64,
+}
+
+impl Standing {
+    fn points(&self) -> u32 {
+        self.won * 3 + self.drawn
+    }
+
+    fn record(&mut self, own: i64, other: i64) {
+        self.played += 1;
+        self.score_for += own;
+        self.score_against += other;
+        match own.cmp(&other) {
+            std::cmp::Ordering::Greater => self.won += 1,
+            std::cmp::Ordering::Equal => self.drawn += 1,
+            std::cmp::Ordering::Less => self.lost += 1,
+        }
+    }
+}
+
+fn play_match(a: &Player, b: &Player, rounds: usize, selection: &mut Selection) -> (i64, i64) {
+    let (mut sa, mut sb) = (0, 0);
+    for i in 0..rounds {
+        let ma = a.shape(i, selection);
+        let mb = b.shape(i, selection);
+        sa += score(mb, ma) as i64;
+        sb += score(ma, mb) as i64;
+    }
+    (sa, sb)
+}
+
+fn tournament(args: &[String]) -> io::Result<()> {
+    let mut rounds = None;
+    let mut selection = Selection::Sequential;
+    let mut players = Vec::new();
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: d02 tournament [--rounds N] [--seed S] FILES...",
+        )
+    };
+    let mut args = args.iter();
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--rounds" => {
+                rounds = Some(
+                    args.next()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .ok_or_else(usage)?,
+                )
+            }
+            "--seed" => {
+                let seed = args
+                    .next()
+                    .and_then(|n| n.parse::<u64>().ok())
+                    .ok_or_else(usage)?;
+                selection = Selection::Random(Rng(seed.max(1)));
+            }
+            flag if flag.starts_with("--") => return Err(usage()),
+            path => {
+                let guide = fs::read_to_string(path)?
+                    .lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .map(parse_line)
+                    .collect::<Vec<_>>();
+                if guide.is_empty() {
+                    let e = format!("empty strategy guide: {}", path);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+                }
+                players.push(Player {
+                    name: path.to_string(),
+                    guide,
+                });
+            }
+        }
+    }
+    let rounds = rounds.unwrap_or_else(|| players.iter().map(|p| p.guide.len()).max().unwrap_or(0));
+
+    let mut table = players
+        .iter()
+        .map(|_| Standing::default())
+        .collect::<Vec<_>>();
+    for a in 0..players.len() {
+        for b in a + 1..players.len() {
+            let (sa, sb) = play_match(&players[a], &players[b], rounds, &mut selection);
+            println!(
+                "{} vs {}: {} - {}",
+                players[a].name, players[b].name, sa, sb
+            );
+            table[a].record(sa, sb);
+            table[b].record(sb, sa);
+        }
+    }
+
+    let mut order = (0..players.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| {
+        let s = &table[i];
+        (
+            std::cmp::Reverse(s.points()),
+            std::cmp::Reverse(s.score_for - s.score_against),
+        )
+    });
+    println!();
+    println!(
+        "{:<20} {:>3} {:>3} {:>3} {:>3} {:>8} {:>8} {:>4}",
+        "strategy", "P", "W", "D", "L", "for", "against", "pts"
+    );
+    for i in order {
+        let s = &table[i];
+        println!(
+            "{:<20} {:>3} {:>3} {:>3} {:>3} {:>8} {:>8} {:>4}",
+            players[i].name,
+            s.played,
+            s.won,
+            s.drawn,
+            s.lost,
+            s.score_for,
+            s.score_against,
+            s.points()
+        );
+    }
+    Ok(())
+}
+
 fn main() -> io::Result<()> {
+    let args = env::args().collect::<Vec<_>>();
+    match args.get(1).map(|a| a.as_str()) {
+        Some("tournament") => return tournament(&args[2..]),
+        None | Some("optimize") => {}
+        _ => {
+            let e = "usage: d02 [optimize | tournament ...]";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, e));
+        }
+    }
+
     let stdin = io::stdin();
 
-    let total: i32 = stdin.lines().map(|l| parse_line(&l.unwrap()) as i32).sum();
+    let rounds = stdin
+        .lines()
+        .collect::<io::Result<Vec<_>>>()?
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| parse_line(l))
+        .collect::<Vec<_>>();
+    if rounds.is_empty() {
+        let e = "empty strategy guide";
+        return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+    }
 
-    println!("total: {}", total);
+    match args.get(1).map(|a| a.as_str()) {
+        Some("optimize") => optimize(&rounds),
+        _ => {
+            let total: i32 = rounds
+                .iter()
+                .map(|&(op, x)| score(op, calc_move(op, x)) as i32)
+                .sum();
+            println!("total: {}", total);
+        }
+    }
 
     Ok(())
 }
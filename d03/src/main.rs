@@ -1,34 +1,504 @@
-use std::collections::HashSet;
+use std::env;
 use std::io;
 
-fn char_to_prio(c: char) -> usize {
+fn char_to_prio(c: char) -> Result<u32, String> {
     match c {
-        x if x >= 'a' && x <= 'z' => x as usize - 'a' as usize + 1,
-        x if x >= 'A' && x <= 'Z' => x as usize - 'A' as usize + 27,
-        _ => panic!("wow"),
+        'a'..='z' => Ok(c as u32 - 'a' as u32 + 1),
+        'A'..='Z' => Ok(c as u32 - 'A' as u32 + 27),
+        _ => Err(format!("invalid item {:?}", c)),
     }
 }
 
+fn prio_to_char(p: u32) -> char {
+    match p {
+        1..=26 => (b'a' + p as u8 - 1) as char,
+        _ => (b'A' + p as u8 - 27) as char,
+    }
+}
+
+/// Items as a bitmask with bit `char_to_prio(c)` set for every item `c`.
+fn to_mask(s: &str) -> Result<u64, String> {
+    s.chars().try_fold(0, |m, c| Ok(m | 1 << char_to_prio(c)?))
+}
+
+fn compartments(l: &str) -> Result<(u64, u64), String> {
+    // Validate first so that splitting below always lands on a char boundary.
+    to_mask(l)?;
+    if !l.len().is_multiple_of(2) {
+        return Err(format!("odd number of items in {:?}", l));
+    }
+    let (a, b) = l.split_at(l.len() / 2);
+    Ok((to_mask(a)?, to_mask(b)?))
+}
+
+/// Priority of the single item in `mask`.
+fn single_prio(mask: u64) -> Result<u32, String> {
+    if mask.count_ones() != 1 {
+        return Err(format!(
+            "expected one common item, found {}",
+            mask.count_ones()
+        ));
+    }
+    Ok(mask.trailing_zeros())
+}
+
+fn solve(lines: &[String], k: usize) -> Result<(u32, u32), String> {
+    let mut part1 = 0;
+    for l in lines {
+        let (a, b) = compartments(l)?;
+        part1 += single_prio(a & b).map_err(|e| format!("{} in {:?}", e, l))?;
+    }
+
+    let masks = lines
+        .iter()
+        .map(|l| to_mask(l))
+        .collect::<Result<Vec<_>, _>>()?;
+    if k == 0 || !masks.len().is_multiple_of(k) {
+        return Err(format!(
+            "{} rucksacks do not form groups of {}",
+            masks.len(),
+            k
+        ));
+    }
+    let mut part2 = 0;
+    for (n, g) in masks.chunks(k).enumerate() {
+        let common = g.iter().fold(!0, |m, r| m & r);
+        part2 += single_prio(common).map_err(|e| format!("{} in group {}", e, n + 1))?;
+    }
+    Ok((part1, part2))
+}
+
+/// Counting groups stops here: the search only needs to tell apart the
+/// rucksacks that have few groups left.
+const MANY: usize = 32;
+
+/// Number of ways, up to `cap`, to pick `need` more of `candidates` so that
+/// the items common to all of them and `common` come down to exactly one.
+fn count_groups(
+    masks: &[u64],
+    candidates: &[usize],
+    need: usize,
+    common: u64,
+    cap: usize,
+) -> usize {
+    if need == 0 {
+        return (common.count_ones() == 1) as usize;
+    }
+    let mut n = 0;
+    for (i, &j) in candidates.iter().enumerate() {
+        if candidates.len() - i < need || n >= cap {
+            break;
+        }
+        let c = common & masks[j];
+        if c != 0 {
+            n += count_groups(masks, &candidates[i + 1..], need - 1, c, cap - n);
+        }
+    }
+    n
+}
+
+/// Exact-cover search that packs every rucksack into a group of `k` that
+/// shares exactly one item. Groups are only built when needed, from unused
+/// rucksacks, dropping a partial group as soon as its members share nothing.
+/// Always branches on the unused rucksack with the fewest groups left, and
+/// backtracks as soon as one has none. Stops after `limit` partitions have
+/// been found.
+struct Discovery<'a> {
+    masks: &'a [u64],
+    k: usize,
+    used: Vec<bool>,
+    limit: usize,
+    current: Vec<Vec<usize>>,
+    found: Vec<Vec<Vec<usize>>>,
+}
+
+impl<'a> Discovery<'a> {
+    fn new(masks: &'a [u64], k: usize, limit: usize) -> Self {
+        Discovery {
+            masks,
+            k,
+            used: vec![false; masks.len()],
+            limit,
+            current: Vec::new(),
+            found: Vec::new(),
+        }
+    }
+
+    /// Unused rucksacks other than `r` that share an item with it.
+    fn partners(&self, r: usize) -> Vec<usize> {
+        (0..self.masks.len())
+            .filter(|&j| j != r && !self.used[j] && self.masks[r] & self.masks[j] != 0)
+            .collect()
+    }
+
+    fn search(&mut self) {
+        let mut pivot: Option<(usize, usize)> = None;
+        for r in (0..self.used.len()).filter(|&r| !self.used[r]) {
+            let cap = pivot.map_or(MANY, |(n, _)| n);
+            let n = count_groups(
+                self.masks,
+                &self.partners(r),
+                self.k - 1,
+                self.masks[r],
+                cap,
+            );
+            if n == 0 {
+                return;
+            }
+            if n < cap || pivot.is_none() {
+                pivot = Some((n, r));
+            }
+        }
+        let Some((_, r)) = pivot else {
+            self.found.push(self.current.clone());
+            return;
+        };
+        let partners = self.partners(r);
+        self.used[r] = true;
+        self.extend(&partners, &mut vec![r], self.masks[r]);
+        self.used[r] = false;
+    }
+
+    /// Completes `group` with members from `candidates` in every possible
+    /// way, searching on from each complete group.
+    fn extend(&mut self, candidates: &[usize], group: &mut Vec<usize>, common: u64) {
+        if group.len() == self.k {
+            if common.count_ones() == 1 {
+                for &m in &group[1..] {
+                    self.used[m] = true;
+                }
+                self.current.push(group.clone());
+                self.search();
+                self.current.pop();
+                for &m in &group[1..] {
+                    self.used[m] = false;
+                }
+            }
+            return;
+        }
+        let missing = self.k - group.len();
+        for (n, &j) in candidates.iter().enumerate() {
+            if candidates.len() - n < missing || self.found.len() >= self.limit {
+                return;
+            }
+            let c = common & self.masks[j];
+            if c != 0 {
+                group.push(j);
+                self.extend(&candidates[n + 1..], group, c);
+                group.pop();
+            }
+        }
+    }
+}
+
+fn discover(lines: &[String], k: usize) -> Result<(), String> {
+    let masks = lines
+        .iter()
+        .map(|l| to_mask(l))
+        .collect::<Result<Vec<_>, _>>()?;
+    if k == 0 || !masks.len().is_multiple_of(k) {
+        return Err(format!(
+            "{} rucksacks do not form groups of {}",
+            masks.len(),
+            k
+        ));
+    }
+    let mut d = Discovery::new(&masks, k, 2);
+    d.search();
+
+    let Some(partition) = d.found.first() else {
+        println!("no partition into groups of {} found", k);
+        return Ok(());
+    };
+    let mut partition = partition.clone();
+    for g in partition.iter_mut() {
+        g.sort();
+    }
+    partition.sort();
+    let mut sum = 0;
+    for g in partition.iter() {
+        let prio = single_prio(g.iter().fold(!0, |m, &i| m & masks[i]))?;
+        sum += prio;
+        let members = g.iter().map(|i| (i + 1).to_string()).collect::<Vec<_>>();
+        println!("lines {}: badge priority {}", members.join(","), prio);
+    }
+    println!("sum: {}", sum);
+    println!(
+        "partition is {}",
+        if d.found.len() == 1 {
+            "unique"
+        } else {
+            "not unique"
+        }
+    );
+    Ok(())
+}
+
+/// Swaps that move every copy of `item` in rucksack `line` from compartment
+/// `from` to compartment `to`, each in exchange for one of `partners`. A
+/// partner is packed exactly once, in `to`, so the compartments keep their
+/// size and no item ends up on both sides. `partners` is `None` if there are
+/// not enough of them.
+struct Fix {
+    line: usize,
+    item: char,
+    count: usize,
+    from: usize,
+    to: usize,
+    partners: Option<Vec<char>>,
+}
+
+struct Analysis {
+    rucksacks: usize,
+    /// Number of rucksacks with the item in both compartments, by priority.
+    duplicated: [u32; 53],
+    everywhere: u64,
+    fixes: Vec<Fix>,
+}
+
+fn analyze(lines: &[String]) -> Result<Analysis, String> {
+    let mut a = Analysis {
+        rucksacks: lines.len(),
+        duplicated: [0; 53],
+        everywhere: if lines.is_empty() { 0 } else { !0 },
+        fixes: Vec::new(),
+    };
+    for (n, l) in lines.iter().enumerate() {
+        let (first, second) = compartments(l)?;
+        a.everywhere &= first | second;
+        let both = first & second;
+        let (left, right) = l.split_at(l.len() / 2);
+        let halves = [left, right];
+        let count = |half: usize, item: char| halves[half].chars().filter(|&c| c == item).count();
+        // Items packed exactly once; each can be swapped away only once.
+        let mut spare = [0, 1].map(|h| {
+            let other = [first, second][1 - h];
+            items([first, second][h] & !other)
+                .into_iter()
+                .filter(|&c| count(h, c) == 1)
+                .collect::<Vec<_>>()
+        });
+        for p in (1..53).filter(|p| both & 1 << p != 0) {
+            a.duplicated[p as usize] += 1;
+            let item = prio_to_char(p);
+            let copies = [count(0, item), count(1, item)];
+            // Move the minority over so the fewest items change compartment,
+            // unless only the other way has enough partners.
+            let mut ways = [(1, 0), (0, 1)];
+            if copies[0] < copies[1] {
+                ways.reverse();
+            }
+            let way = ways
+                .iter()
+                .find(|&&(from, to)| spare[to].len() >= copies[from])
+                .unwrap_or(&ways[0]);
+            let (from, to) = *way;
+            let partners = (spare[to].len() >= copies[from])
+                .then(|| spare[to].drain(..copies[from]).collect());
+            a.fixes.push(Fix {
+                line: n + 1,
+                item,
+                count: copies[from],
+                from: from + 1,
+                to: to + 1,
+                partners,
+            });
+        }
+    }
+    Ok(a)
+}
+
+fn items(mask: u64) -> Vec<char> {
+    (1..53)
+        .filter(|p| mask & 1 << p != 0)
+        .map(prio_to_char)
+        .collect()
+}
+
+fn print_table(a: &Analysis) {
+    let mut by_count = (1..53).filter(|&p| a.duplicated[p] > 0).collect::<Vec<_>>();
+    by_count.sort_by_key(|&p| (std::cmp::Reverse(a.duplicated[p]), p));
+    println!("rucksacks: {}", a.rucksacks);
+    let everywhere = items(a.everywhere).into_iter().collect::<String>();
+    println!(
+        "in every rucksack: {}",
+        if everywhere.is_empty() {
+            "(none)"
+        } else {
+            &everywhere
+        }
+    );
+    println!();
+    println!(
+        "{:<4} {:>4} {:>10} {:>6}",
+        "item", "prio", "duplicated", "total"
+    );
+    for p in by_count {
+        println!(
+            "{:<4} {:>4} {:>10} {:>6}",
+            prio_to_char(p as u32),
+            p,
+            a.duplicated[p],
+            a.duplicated[p] * p as u32
+        );
+    }
+    println!();
+    for f in a.fixes.iter() {
+        match &f.partners {
+            Some(partners) => {
+                for p in partners {
+                    println!(
+                        "line {}: swap {} in compartment {} with {} in compartment {}",
+                        f.line, f.item, f.from, p, f.to
+                    );
+                }
+            }
+            None => println!(
+                "line {}: cannot move {} x {} from compartment {} to {}: too few items only in compartment {}",
+                f.line, f.count, f.item, f.from, f.to, f.to
+            ),
+        }
+    }
+}
+
+fn print_json(a: &Analysis) {
+    let duplicated = (1..53)
+        .filter(|&p| a.duplicated[p] > 0)
+        .map(|p| {
+            format!(
+                "{{\"item\":\"{}\",\"priority\":{},\"duplicated\":{},\"total\":{}}}",
+                prio_to_char(p as u32),
+                p,
+                a.duplicated[p],
+                a.duplicated[p] * p as u32
+            )
+        })
+        .collect::<Vec<_>>();
+    let everywhere = items(a.everywhere)
+        .iter()
+        .map(|c| format!("\"{}\"", c))
+        .collect::<Vec<_>>();
+    let fixes = a
+        .fixes
+        .iter()
+        .map(|f| {
+            let partners = match &f.partners {
+                Some(p) => {
+                    let p = p.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>();
+                    format!("[{}]", p.join(","))
+                }
+                None => "null".to_string(),
+            };
+            format!(
+                "{{\"line\":{},\"item\":\"{}\",\"count\":{},\"from\":{},\"to\":{},\"swap_with\":{}}}",
+                f.line, f.item, f.count, f.from, f.to, partners
+            )
+        })
+        .collect::<Vec<_>>();
+    println!(
+        "{{\"rucksacks\":{},\"everywhere\":[{}],\"priorities\":[{}],\"swaps\":[{}]}}",
+        a.rucksacks,
+        everywhere.join(","),
+        duplicated.join(","),
+        fixes.join(",")
+    );
+}
+
+enum Mode {
+    Solve,
+    Discover,
+    Analyze,
+}
+
 fn main() -> io::Result<()> {
     let stdin = io::stdin();
 
-    let lines = stdin
-        .lines()
-        .map(|l| l.unwrap().chars().collect::<HashSet<_>>())
-        .collect::<Vec<_>>();
-    assert!(lines.len() % 3 == 0);
-    let mut sum = 0;
-    for n in (0..lines.len()).step_by(3) {
-        let m = lines[n]
-            .intersection(&lines[n + 1])
-            .map(|c| *c)
-            .collect::<HashSet<_>>();
-        let m = m.intersection(&lines[n + 2]).collect::<Vec<_>>();
-        assert!(m.len() == 1);
-        sum += char_to_prio(*m[0]);
+    let mut mode = Mode::Solve;
+    let mut k = 3;
+    let mut json = false;
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: d03 [discover|analyze [--json]] [--group K]",
+        )
+    };
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "discover" => mode = Mode::Discover,
+            "analyze" => mode = Mode::Analyze,
+            "--group" => {
+                k = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&k| k > 0)
+                    .ok_or_else(usage)?
+            }
+            "--json" => json = true,
+            _ => return Err(usage()),
+        }
     }
 
-    println!("sum: {}", sum);
+    let lines = stdin.lines().collect::<io::Result<Vec<_>>>()?;
+    let to_io = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+    match mode {
+        Mode::Solve => {
+            let (part1, part2) = solve(&lines, k).map_err(to_io)?;
+            println!("compartments sum: {}", part1);
+            println!("sum: {}", part2);
+        }
+        Mode::Discover => discover(&lines, k).map_err(to_io)?,
+        Mode::Analyze => {
+            let a = analyze(&lines).map_err(to_io)?;
+            if json {
+                print_json(&a);
+            } else {
+                print_table(&a);
+            }
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `full.txt` in a fixed pseudo-random order, so the real groups are no
+    /// longer next to each other.
+    fn shuffled() -> Vec<u64> {
+        let mut masks = include_str!("../full.txt")
+            .lines()
+            .map(|l| to_mask(l).unwrap())
+            .collect::<Vec<_>>();
+        let mut state = 0x2545f4914f6cdd1d_u64;
+        for i in (1..masks.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            masks.swap(i, (state % (i as u64 + 1)) as usize);
+        }
+        masks
+    }
+
+    #[test]
+    fn discovers_groups_in_shuffled_input() {
+        let masks = shuffled();
+        for k in [3, 4, 6] {
+            let mut d = Discovery::new(&masks, k, 1);
+            d.search();
+            let partition = &d.found[0];
+            let mut seen = vec![false; masks.len()];
+            for g in partition {
+                assert_eq!(g.len(), k);
+                let common = g.iter().fold(!0, |m, &i| m & masks[i]);
+                assert_eq!(common.count_ones(), 1);
+                for &i in g {
+                    assert!(!std::mem::replace(&mut seen[i], true));
+                }
+            }
+            assert!(seen.iter().all(|&s| s));
+        }
+    }
+}
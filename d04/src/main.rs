@@ -1,38 +1,249 @@
-use std::collections::HashSet;
+use intervals::IntervalSet;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::env;
 use std::io;
 use std::ops::RangeInclusive;
 
-fn parse_line(l: &str) -> (RangeInclusive<i32>, RangeInclusive<i32>) {
-    let mut ranges = l.split(",").map(|s| parse_range(s));
+fn parse_line(l: &str) -> (RangeInclusive<i64>, RangeInclusive<i64>) {
+    let mut ranges = l.split(',').map(parse_range);
     let result = ranges.next().unwrap();
     let result = (result, ranges.next().unwrap());
     assert!(ranges.next().is_none());
     result
 }
 
-fn parse_range(s: &str) -> RangeInclusive<i32> {
-    let mut num = s.split("-").map(|n| n.parse::<i32>().unwrap());
+/// A section range like `2-4`, or `-` for an elf without an assignment,
+/// which is read as an empty range.
+fn parse_range(s: &str) -> RangeInclusive<i64> {
+    if s == "-" {
+        return DROPPED;
+    }
+    let mut num = s.split('-').map(|n| n.parse::<i64>().unwrap());
     let start = num.next().unwrap();
     let end = num.next().unwrap();
     assert!(num.next().is_none());
     start..=end
 }
 
-fn fully_overlap(a: &RangeInclusive<i32>, b: &RangeInclusive<i32>) -> bool {
-    (a.contains(b.start()) && a.contains(b.end())) || (b.contains(a.start()) && b.contains(a.end()))
+#[allow(clippy::reversed_empty_ranges)]
+const DROPPED: RangeInclusive<i64> = 1..=0;
+
+/// An elf without sections neither contains nor is contained in another.
+fn fully_overlap(a: &RangeInclusive<i64>, b: &RangeInclusive<i64>) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    let (a, b) = (IntervalSet::from(a.clone()), IntervalSet::from(b.clone()));
+    a.is_superset(&b) || b.is_superset(&a)
+}
+
+fn some_overlap(a: &RangeInclusive<i64>, b: &RangeInclusive<i64>) -> bool {
+    IntervalSet::from(a.clone()).overlaps(&IntervalSet::from(b.clone()))
+}
+
+/// Elf `elf` (0 or 1) of input line `line`, both zero based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Elf {
+    line: usize,
+    elf: usize,
+}
+
+impl std::fmt::Display for Elf {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {} elf {}", self.line + 1, self.elf + 1)
+    }
+}
+
+fn assignments(
+    ranges: &[(RangeInclusive<i64>, RangeInclusive<i64>)],
+) -> Vec<(Elf, RangeInclusive<i64>)> {
+    ranges
+        .iter()
+        .enumerate()
+        .flat_map(|(line, (a, b))| {
+            [
+                (Elf { line, elf: 0 }, a.clone()),
+                (Elf { line, elf: 1 }, b.clone()),
+            ]
+        })
+        .collect()
+}
+
+/// Maximum number of elves on any section, and the sections where it is reached.
+fn max_coverage(assignments: &[(Elf, RangeInclusive<i64>)]) -> (usize, IntervalSet) {
+    let mut events = Vec::new();
+    for (_, r) in assignments.iter() {
+        events.push((*r.start(), 1));
+        events.push((r.end() + 1, -1));
+    }
+    events.sort();
+
+    let mut count = 0i64;
+    let mut max = 0;
+    let mut at = IntervalSet::new();
+    for (i, (x, delta)) in events.iter().enumerate() {
+        count += delta;
+        let next = match events.get(i + 1) {
+            Some((n, _)) if n > x => *n,
+            _ => continue,
+        };
+        if count > max {
+            max = count;
+            at = IntervalSet::new();
+        }
+        if count == max && max > 0 {
+            at.insert(*x..=next - 1);
+        }
+    }
+    (max as usize, at)
+}
+
+/// All pairs of elves with overlapping assignments. Sorting by start and
+/// keeping the currently open assignments in a heap ordered by end makes this
+/// O(n log n) plus the number of pairs reported.
+fn overlapping_pairs(assignments: &[(Elf, RangeInclusive<i64>)]) -> Vec<(Elf, Elf)> {
+    let mut order = (0..assignments.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| *assignments[i].1.start());
+
+    let mut open: BinaryHeap<Reverse<(i64, usize)>> = BinaryHeap::new();
+    let mut pairs = Vec::new();
+    for i in order {
+        let (elf, r) = &assignments[i];
+        if r.is_empty() {
+            continue;
+        }
+        while let Some(Reverse((end, _))) = open.peek() {
+            if end >= r.start() {
+                break;
+            }
+            open.pop();
+        }
+        for Reverse((_, j)) in open.iter() {
+            let other = assignments[*j].0;
+            pairs.push((other.min(*elf), other.max(*elf)));
+        }
+        open.push(Reverse((*r.end(), i)));
+    }
+    pairs.sort();
+    pairs
 }
 
-fn some_overlap(a: &RangeInclusive<i32>, b: &RangeInclusive<i32>) -> bool {
-    a.contains(b.start()) || a.contains(b.end()) || b.contains(a.start()) || b.contains(a.end())
+fn crew(ranges: &[(RangeInclusive<i64>, RangeInclusive<i64>)]) {
+    let assignments = assignments(ranges);
+    let all = assignments
+        .iter()
+        .map(|(_, r)| r.clone())
+        .collect::<IntervalSet>();
+    let (Some(first), Some(last)) = (all.ranges().first(), all.ranges().last()) else {
+        return;
+    };
+    let uncovered = IntervalSet::from(*first.start()..=*last.end()).difference(&all);
+
+    let (max, at) = max_coverage(&assignments);
+    println!("most covered: {} elves on {}", max, format_set(&at));
+    println!("uncovered: {}", format_set(&uncovered));
+
+    let pairs = overlapping_pairs(&assignments);
+    println!("overlapping pairs: {}", pairs.len());
+    for (a, b) in pairs {
+        println!("  {} and {}", a, b);
+    }
+}
+
+fn format_set(s: &IntervalSet) -> String {
+    if s.is_empty() {
+        return "none".to_string();
+    }
+    s.ranges()
+        .iter()
+        .map(|r| format!("{}-{}", r.start(), r.end()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Shrinks or drops assignments so that every section stays covered by
+/// exactly one elf. Greedily handing each uncovered section to the elf that
+/// reaches furthest keeps total work at the size of the covered area, the
+/// minimum possible, and uses as few elves as possible.
+fn reassign(assignments: &[(Elf, RangeInclusive<i64>)]) -> Vec<Option<RangeInclusive<i64>>> {
+    let mut order = (0..assignments.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| (*assignments[i].1.start(), Reverse(*assignments[i].1.end())));
+    let range = |i: usize| &assignments[i].1;
+
+    let mut result = vec![None; assignments.len()];
+    let mut cur = i64::MIN;
+    let mut i = 0;
+    while i < order.len() {
+        if range(order[i]).is_empty() || *range(order[i]).end() < cur {
+            i += 1;
+            continue;
+        }
+        cur = cur.max(*range(order[i]).start());
+        let mut best = order[i];
+        while i < order.len() && *range(order[i]).start() <= cur {
+            if range(order[i]).end() > range(best).end() {
+                best = order[i];
+            }
+            i += 1;
+        }
+        result[best] = Some(cur..=*range(best).end());
+        cur = range(best).end() + 1;
+    }
+    result
+}
+
+fn print_reassignment(ranges: &[(RangeInclusive<i64>, RangeInclusive<i64>)]) {
+    let assignments = assignments(ranges);
+    let new = reassign(&assignments);
+    let work = |r: &RangeInclusive<i64>| IntervalSet::from(r.clone()).len();
+    let before: u64 = assignments.iter().map(|(_, r)| work(r)).sum();
+    let after: u64 = new.iter().flatten().map(work).sum();
+    let dropped = new.iter().filter(|r| r.is_none()).count();
+    let shrunk = assignments
+        .iter()
+        .zip(new.iter())
+        .filter(|((_, old), new)| matches!(new, Some(n) if n != old))
+        .count();
+    eprintln!(
+        "work: {} -> {}, dropped: {}, shrunk: {}",
+        before, after, dropped, shrunk
+    );
+
+    // A dropped elf is written as `-`, which parses as input again.
+    let format = |elf: usize| match &new[elf] {
+        Some(r) => format!("{}-{}", r.start(), r.end()),
+        None => "-".to_string(),
+    };
+    for line in 0..ranges.len() {
+        println!("{},{}", format(2 * line), format(2 * line + 1));
+    }
 }
 
 fn main() -> io::Result<()> {
+    let mode = env::args().nth(1);
+    if !matches!(mode.as_deref(), None | Some("crew" | "reassign")) || env::args().nth(2).is_some()
+    {
+        let e = "usage: d04 [crew|reassign]";
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, e));
+    }
     let stdin = io::stdin();
 
     let ranges = stdin
         .lines()
         .map(|l| parse_line(&l.unwrap()))
         .collect::<Vec<_>>();
+    match mode.as_deref() {
+        Some("crew") => {
+            crew(&ranges);
+            return Ok(());
+        }
+        Some("reassign") => {
+            print_reassignment(&ranges);
+            return Ok(());
+        }
+        _ => {}
+    }
     let full = ranges.iter().filter(|(a, b)| fully_overlap(a, b)).count();
     let some = ranges.iter().filter(|(a, b)| some_overlap(a, b)).count();
 
@@ -0,0 +1,145 @@
+//! Parsing and rendering of the stack drawing at the top of the input.
+//!
+//! Stack positions come from the numbered footer line, so any number of
+//! stacks, labels of any width, multi-letter crates and ragged right edges
+//! are supported.
+
+pub type Stacks = Vec<Vec<String>>;
+
+/// A `[...]` crate or footer label and the columns it occupies.
+struct Token {
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+fn footer_tokens(l: &str) -> Option<Vec<Token>> {
+    if !l.chars().all(|c| c.is_ascii_digit() || c == ' ') || l.trim().is_empty() {
+        return None;
+    }
+    let mut r = Vec::new();
+    let mut start = None;
+    for (i, c) in l.chars().chain([' ']).enumerate() {
+        match (c.is_ascii_digit(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                r.push(Token {
+                    text: l[s..i].to_string(),
+                    start: s,
+                    end: i,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    Some(r)
+}
+
+fn crate_tokens(l: &str) -> Result<Vec<Token>, String> {
+    let mut r = Vec::new();
+    let mut i = 0;
+    while let Some(c) = l[i..].chars().next() {
+        match c {
+            ' ' => i += 1,
+            '[' => {
+                let close = l[i..]
+                    .find(']')
+                    .ok_or_else(|| format!("unclosed crate at column {}", i + 1))?;
+                let text = &l[i + 1..i + close];
+                if text.is_empty() || text.contains(['[', ' ']) {
+                    return Err(format!("bad crate {:?} at column {}", text, i + 1));
+                }
+                r.push(Token {
+                    text: text.to_string(),
+                    start: i,
+                    end: i + close + 1,
+                });
+                i += close + 1;
+            }
+            _ => return Err(format!("unexpected {:?} at column {}", c, i + 1)),
+        }
+    }
+    Ok(r)
+}
+
+/// Whether `l` is the numbered footer that ends the drawing.
+pub fn is_footer(l: &str) -> bool {
+    footer_tokens(l).is_some()
+}
+
+/// Parses the drawing including its footer into stacks, bottom crate first.
+pub fn parse(lines: &[String]) -> Result<Stacks, String> {
+    let (footer, rows) = lines
+        .split_last()
+        .ok_or_else(|| "empty drawing".to_string())?;
+    let labels = footer_tokens(footer).ok_or_else(|| format!("bad footer {:?}", footer))?;
+    for (i, l) in labels.iter().enumerate() {
+        if l.text != (i + 1).to_string() {
+            return Err(format!(
+                "expected stack {} in footer, found {}",
+                i + 1,
+                l.text
+            ));
+        }
+    }
+
+    let mut stacks = vec![Vec::new(); labels.len()];
+    for (n, row) in rows.iter().enumerate().rev() {
+        for t in crate_tokens(row).map_err(|e| format!("drawing line {}: {}", n + 1, e))? {
+            // Take the label closest to the crate's centre.
+            let centre = t.start + t.end;
+            let stack = (0..labels.len())
+                .min_by_key(|&i| (labels[i].start + labels[i].end).abs_diff(centre))
+                .ok_or_else(|| format!("drawing line {}: no stacks", n + 1))?;
+            if stacks[stack].len() != rows.len() - 1 - n {
+                return Err(format!(
+                    "drawing line {}: crate {} floats above stack {}",
+                    n + 1,
+                    t.text,
+                    stack + 1
+                ));
+            }
+            stacks[stack].push(t.text);
+        }
+    }
+    Ok(stacks)
+}
+
+/// Renders stacks in the input format: crates in `[...]`, one space between
+/// columns, trailing blanks trimmed from crate rows and a numbered footer.
+pub fn render(stacks: &Stacks) -> String {
+    let width = stacks
+        .iter()
+        .flatten()
+        .map(|c| c.chars().count() + 2)
+        .chain([3, stacks.len().to_string().len()])
+        .max()
+        .unwrap();
+    let centre = |s: &str| {
+        let pad = width - s.chars().count();
+        format!("{}{}{}", " ".repeat(pad / 2), s, " ".repeat(pad - pad / 2))
+    };
+
+    let height = stacks.iter().map(|s| s.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for level in (0..height).rev() {
+        let row = stacks
+            .iter()
+            .map(|s| match s.get(level) {
+                Some(c) => centre(&format!("[{}]", c)),
+                None => " ".repeat(width),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        out.push_str(row.trim_end());
+        out.push('\n');
+    }
+    let footer = (1..=stacks.len())
+        .map(|i| centre(&i.to_string()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    out.push_str(&footer);
+    out.push('\n');
+    out
+}
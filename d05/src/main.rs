@@ -1,89 +1,320 @@
-use std::collections::HashSet;
+mod drawing;
+mod planner;
+
+use drawing::Stacks;
+use std::env;
 use std::io;
-use std::ops::RangeInclusive;
-
-fn parse_crates(l: &str) -> Vec<char> {
-    let mut r = Vec::new();
-    let mut c = l.chars();
-    loop {
-        if c.next() == None {
-            break;
-        }
-        r.push(c.next().unwrap());
-        c.next();
-        c.next();
-    }
-    r
-}
 
 #[derive(Debug)]
 struct Move {
     num: usize,
     from: usize,
     to: usize,
+    /// Input line the move was read from, for error messages.
+    line: usize,
 }
 
-fn parse_move(l: &str) -> Move {
-    let parts = l.split(" ").collect::<Vec<_>>();
-    assert!(parts[0] == "move");
-    assert!(parts[2] == "from");
-    assert!(parts[4] == "to");
-    Move {
-        num: parts[1].parse::<usize>().unwrap(),
-        from: parts[3].parse::<usize>().unwrap(),
-        to: parts[5].parse::<usize>().unwrap(),
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "move {} from {} to {}", self.num, self.from, self.to)
     }
 }
 
-fn main() -> io::Result<()> {
-    let stdin = io::stdin();
-    let mut lines = stdin.lines();
+fn parse_move(l: &str, line: usize) -> Result<Move, String> {
+    let parts = l.split(' ').collect::<Vec<_>>();
+    let number = |i: usize| parts.get(i).and_then(|p| p.parse::<usize>().ok());
+    match (parts.as_slice(), number(1), number(3), number(5)) {
+        (["move", _, "from", _, "to", _], Some(num), Some(from), Some(to)) => Ok(Move {
+            num,
+            from,
+            to,
+            line,
+        }),
+        _ => Err(format!("line {}: bad move {:?}", line, l)),
+    }
+}
 
-    let mut stacks = Vec::new();
+/// Checks that `m` can be carried out on `stacks`.
+fn validate(stacks: &Stacks, m: &Move) -> Result<(), String> {
+    for s in [m.from, m.to] {
+        if s == 0 || s > stacks.len() {
+            return Err(format!(
+                "line {}: {}: no stack {}, there are {}",
+                m.line,
+                m,
+                s,
+                stacks.len()
+            ));
+        }
+    }
+    let available = stacks[m.from - 1].len();
+    if m.num > available {
+        return Err(format!(
+            "line {}: {}: stack {} only has {} crates",
+            m.line, m, m.from, available
+        ));
+    }
+    Ok(())
+}
+
+/// A crane moves crates in lifts of at most `capacity()` crates. Crates
+/// within one lift keep their order, successive lifts pile on top.
+trait Crane {
+    fn name(&self) -> String;
+
+    fn capacity(&self) -> usize;
 
-    loop {
-        let l = lines.next().unwrap().unwrap();
-        if l.starts_with(" 1 ") {
-            break;
+    /// Carries out `m` and returns the moved crates in their original order,
+    /// which is all that is needed to undo it. `m` has to be valid.
+    fn apply(&self, stacks: &mut Stacks, m: &Move) -> Vec<String> {
+        let from = &stacks[m.from - 1];
+        let moved = from[from.len() - m.num..].to_vec();
+        let mut left = m.num;
+        while left > 0 {
+            let n = left.min(self.capacity());
+            let from = &mut stacks[m.from - 1];
+            let lift = from.split_off(from.len() - n);
+            stacks[m.to - 1].extend(lift);
+            left -= n;
         }
-        let crates = parse_crates(&l);
-        if stacks.len() == 0 {
-            // first iteration.
-            for _ in 0..crates.len() {
-                stacks.push(Vec::new());
-            }
+        moved
+    }
+}
+
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn capacity(&self) -> usize {
+        1
+    }
+}
+
+struct CrateMover9001 {
+    capacity: usize,
+}
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        if self.capacity == usize::MAX {
+            "CrateMover 9001".to_string()
+        } else {
+            format!("CrateMover 9001 (max {} per lift)", self.capacity)
         }
-        for (i, c) in crates.into_iter().enumerate() {
-            if c != ' ' {
-                stacks[i].push(c);
-            }
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+/// Steps forwards and backwards through the moves of a puzzle.
+struct Stepper<'a> {
+    crane: &'a dyn Crane,
+    moves: &'a [Move],
+    stacks: Stacks,
+    undo: Vec<Vec<String>>,
+}
+
+impl<'a> Stepper<'a> {
+    fn new(crane: &'a dyn Crane, stacks: Stacks, moves: &'a [Move]) -> Self {
+        Stepper {
+            crane,
+            moves,
+            stacks,
+            undo: Vec::new(),
         }
     }
-    println!("{:?}", stacks);
-    for i in 0..stacks.len() {
-        stacks[i].reverse();
-    }
-    println!("{:?}", stacks);
-    lines.next();
-    for l in lines {
-        let l = l.unwrap();
-        let m = parse_move(&l);
-        println!("{:?}", m);
-        let mut crane = Vec::new();
-        for _ in 0..m.num {
-            let c = stacks[m.from - 1].pop().unwrap();
-            crane.push(c);
+
+    /// Number of moves carried out so far.
+    fn position(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Carries out the next move. Returns `Ok(false)` when all moves are done.
+    fn forward(&mut self) -> Result<bool, String> {
+        let Some(m) = self.moves.get(self.position()) else {
+            return Ok(false);
+        };
+        validate(&self.stacks, m)?;
+        let moved = self.crane.apply(&mut self.stacks, m);
+        self.undo.push(moved);
+        Ok(true)
+    }
+
+    /// Takes back the last move. Returns `false` at the start.
+    fn back(&mut self) -> bool {
+        let Some(moved) = self.undo.pop() else {
+            return false;
+        };
+        let m = &self.moves[self.position()];
+        let to = &mut self.stacks[m.to - 1];
+        to.truncate(to.len() - m.num);
+        self.stacks[m.from - 1].extend(moved);
+        true
+    }
+
+    fn run(&mut self) -> Result<(), String> {
+        while self.forward()? {}
+        Ok(())
+    }
+}
+
+fn top(stacks: &Stacks) -> String {
+    stacks.iter().filter_map(|s| s.last().cloned()).collect()
+}
+
+struct Puzzle {
+    stacks: Stacks,
+    moves: Vec<Move>,
+}
+
+fn parse_puzzle(lines: &[String]) -> Result<Puzzle, String> {
+    let footer = lines
+        .iter()
+        .position(|l| drawing::is_footer(l))
+        .ok_or_else(|| "no numbered footer below the drawing".to_string())?;
+    let stacks = drawing::parse(&lines[..=footer])?;
+    let moves = lines
+        .iter()
+        .enumerate()
+        .skip(footer + 1)
+        .filter(|(_, l)| !l.is_empty())
+        .map(|(n, l)| parse_move(l, n + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Puzzle { stacks, moves })
+}
+
+/// Interactive stepping: reads commands from stdin and shows the drawing
+/// after each one. `n` (or an empty line) steps forward, `b` steps back,
+/// `g N` goes to move N and `q` quits.
+fn step(crane: &dyn Crane, puzzle: &Puzzle) -> Result<(), String> {
+    let mut stepper = Stepper::new(crane, puzzle.stacks.clone(), &puzzle.moves);
+    print!("{}", drawing::render(&stepper.stacks));
+    for cmd in io::stdin().lines() {
+        let cmd = cmd.map_err(|e| e.to_string())?;
+        let mut words = cmd.split_whitespace();
+        match (
+            words.next(),
+            words.next().and_then(|n| n.parse::<usize>().ok()),
+        ) {
+            (None | Some("n"), _) => {
+                stepper.forward()?;
+            }
+            (Some("b"), _) => {
+                stepper.back();
+            }
+            (Some("g"), Some(n)) => {
+                while stepper.position() > n && stepper.back() {}
+                while stepper.position() < n && stepper.forward()? {}
+            }
+            (Some("q"), _) => break,
+            _ => {
+                println!("commands: n, b, g N, q");
+                continue;
+            }
         }
-        crane.reverse();
-        for c in crane {
-            stacks[m.to - 1].push(c);
+        let pos = stepper.position();
+        match pos {
+            0 => println!("start"),
+            _ => println!("{}/{}: {}", pos, puzzle.moves.len(), puzzle.moves[pos - 1]),
         }
-        println!("{:?}", stacks);
+        print!("{}", drawing::render(&stepper.stacks));
     }
-    for mut s in stacks {
-        print!("{}", s.pop().unwrap());
+    Ok(())
+}
+
+/// Prints the start drawing followed by a shortest move list reaching
+/// `target`, so the output is a puzzle that can be fed back in. The plan is
+/// replayed through a `Stepper` before printing.
+fn plan(crane: &dyn Crane, start: &Stacks, target: &Stacks, limit: usize) -> Result<(), String> {
+    let moves = planner::plan(crane, start, target, limit)?;
+    let mut stepper = Stepper::new(crane, start.clone(), &moves);
+    stepper.run()?;
+    if &stepper.stacks != target {
+        return Err("plan does not reach the target".to_string());
     }
+    eprintln!("{}: {} moves", crane.name(), moves.len());
+    print!("{}", drawing::render(start));
     println!();
+    for m in moves.iter() {
+        println!("{}", m);
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    let mut cranes: Vec<Box<dyn Crane>> = vec![
+        Box::new(CrateMover9000),
+        Box::new(CrateMover9001 {
+            capacity: usize::MAX,
+        }),
+    ];
+    let mut step_file = None;
+    let mut target_file = None;
+    let mut limit = 1_000_000;
+    let mut chosen: Box<dyn Crane> = Box::new(CrateMover9001 {
+        capacity: usize::MAX,
+    });
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: d05 [step FILE | plan TARGET [--limit N]] [--capacity K | --9000]",
+        )
+    };
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--capacity" => {
+                let capacity = args
+                    .next()
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|&c| c > 0)
+                    .ok_or_else(usage)?;
+                cranes.push(Box::new(CrateMover9001 { capacity }));
+                chosen = Box::new(CrateMover9001 { capacity });
+            }
+            "--9000" => chosen = Box::new(CrateMover9000),
+            "step" => step_file = Some(args.next().ok_or_else(usage)?),
+            "plan" => target_file = Some(args.next().ok_or_else(usage)?),
+            "--limit" => limit = args.next().and_then(|n| n.parse().ok()).ok_or_else(usage)?,
+            _ => return Err(usage()),
+        }
+    }
+    let to_io = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+
+    if let Some(path) = step_file {
+        let lines = std::fs::read_to_string(path)?
+            .lines()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        let puzzle = parse_puzzle(&lines).map_err(to_io)?;
+        return step(chosen.as_ref(), &puzzle).map_err(to_io);
+    }
+
+    let stdin = io::stdin();
+    let lines = stdin.lines().collect::<io::Result<Vec<_>>>()?;
+    let puzzle = parse_puzzle(&lines).map_err(to_io)?;
+
+    if let Some(path) = target_file {
+        let lines = std::fs::read_to_string(path)?
+            .lines()
+            .map(|l| l.to_string())
+            .collect::<Vec<_>>();
+        let target = parse_puzzle(&lines).map_err(to_io)?.stacks;
+        return plan(chosen.as_ref(), &puzzle.stacks, &target, limit).map_err(to_io);
+    }
+
+    print!("{}", drawing::render(&puzzle.stacks));
+
+    for crane in cranes {
+        let mut stepper = Stepper::new(crane.as_ref(), puzzle.stacks.clone(), &puzzle.moves);
+        stepper.run().map_err(to_io)?;
+        println!("{}: {}", crane.name(), top(&stepper.stacks));
+    }
 
     Ok(())
 }
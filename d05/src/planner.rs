@@ -0,0 +1,116 @@
+//! A* search for the shortest list of moves from one drawing to another.
+
+use crate::drawing::Stacks;
+use crate::{Crane, Move};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Lower bound on the moves still needed: every stack holding a crate above
+/// the part that already matches the target needs a move out of it, and
+/// every stack missing crates needs a move into it. One move has one source
+/// and one destination.
+fn estimate(stacks: &Stacks, target: &Stacks) -> usize {
+    let mut sources = 0;
+    let mut destinations = 0;
+    for (s, t) in stacks.iter().zip(target.iter()) {
+        let matching = s.iter().zip(t.iter()).take_while(|(a, b)| a == b).count();
+        if s.len() > matching {
+            sources += 1;
+        }
+        if t.len() > matching {
+            destinations += 1;
+        }
+    }
+    sources.max(destinations)
+}
+
+fn sorted_crates(stacks: &Stacks) -> Vec<&String> {
+    let mut r = stacks.iter().flatten().collect::<Vec<_>>();
+    r.sort();
+    r
+}
+
+/// Finds a shortest move list turning `start` into `target` with `crane`.
+/// Gives up after expanding `limit` arrangements.
+pub fn plan(
+    crane: &dyn Crane,
+    start: &Stacks,
+    target: &Stacks,
+    limit: usize,
+) -> Result<Vec<Move>, String> {
+    if start.len() != target.len() {
+        return Err(format!(
+            "start has {} stacks, target has {}",
+            start.len(),
+            target.len()
+        ));
+    }
+    if sorted_crates(start) != sorted_crates(target) {
+        return Err("start and target hold different crates".to_string());
+    }
+
+    // Arrangements seen so far with the move that first reached them.
+    let mut states: Vec<(Stacks, Option<(usize, Move)>)> = vec![(start.clone(), None)];
+    let mut index = HashMap::from([(start.clone(), 0)]);
+    let mut cost = vec![0];
+    let mut queue = BinaryHeap::from([Reverse((estimate(start, target), 0, 0))]);
+    let mut expanded = 0;
+
+    while let Some(Reverse((_, g, id))) = queue.pop() {
+        if g > cost[id] {
+            continue;
+        }
+        if &states[id].0 == target {
+            let mut moves = Vec::new();
+            let mut cur = id;
+            while let Some((prev, m)) = &states[cur].1 {
+                moves.push(Move {
+                    num: m.num,
+                    from: m.from,
+                    to: m.to,
+                    line: 0,
+                });
+                cur = *prev;
+            }
+            moves.reverse();
+            return Ok(moves);
+        }
+        expanded += 1;
+        if expanded > limit {
+            return Err(format!("no plan found within {} arrangements", limit));
+        }
+
+        let stacks = states[id].0.clone();
+        for from in 0..stacks.len() {
+            for to in (0..stacks.len()).filter(|&to| to != from) {
+                for num in 1..=stacks[from].len() {
+                    let m = Move {
+                        num,
+                        from: from + 1,
+                        to: to + 1,
+                        line: 0,
+                    };
+                    let mut next = stacks.clone();
+                    crane.apply(&mut next, &m);
+                    let next_id = match index.get(&next) {
+                        Some(&i) if cost[i] <= g + 1 => continue,
+                        Some(&i) => {
+                            states[i].1 = Some((id, m));
+                            cost[i] = g + 1;
+                            i
+                        }
+                        None => {
+                            index.insert(next.clone(), states.len());
+                            states.push((next, Some((id, m))));
+                            cost.push(g + 1);
+                            states.len() - 1
+                        }
+                    };
+                    let f = g + 1 + estimate(&states[next_id].0, target);
+                    queue.push(Reverse((f, g + 1, next_id)));
+                }
+            }
+        }
+    }
+    Err("target cannot be reached".to_string())
+}
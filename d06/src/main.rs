@@ -1,31 +1,162 @@
-use std::collections::{HashSet, VecDeque};
-use std::io;
-use std::ops::RangeInclusive;
+use std::collections::VecDeque;
+use std::env;
+use std::io::{self, BufWriter, Read, Write};
 
-fn parse_line(l: &str) -> usize {
-    let mut b = VecDeque::new();
-    const L: usize = 14;
+/// Sliding window over the last `len` bytes that keeps a count per byte
+/// value and the number of distinct values, so every step is O(1).
+struct Detector {
+    len: usize,
+    window: VecDeque<u8>,
+    counts: [u32; 256],
+    distinct: usize,
+}
 
-    for (i, c) in l.chars().enumerate() {
-        if i >= L {
-            b.pop_front();
+impl Detector {
+    fn new(len: usize) -> Self {
+        Detector {
+            len,
+            window: VecDeque::with_capacity(len + 1),
+            counts: [0; 256],
+            distinct: 0,
         }
-        b.push_back(c);
-        let mut s = HashSet::new();
-        for x in b.iter() {
-            s.insert(x);
+    }
+
+    /// Feeds the next byte. Returns whether the last `len` bytes are all
+    /// different.
+    fn push(&mut self, b: u8) -> bool {
+        self.window.push_back(b);
+        self.counts[b as usize] += 1;
+        if self.counts[b as usize] == 1 {
+            self.distinct += 1;
         }
-        if s.len() == L {
-            return i + 1;
+        if self.window.len() > self.len {
+            let old = self.window.pop_front().unwrap() as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 0 {
+                self.distinct -= 1;
+            }
         }
+        self.distinct == self.len
     }
-    panic!("damn!");
+}
+
+/// Position after the first marker of each length in `lens`.
+fn parse_line(l: &str, lens: &[usize]) -> Vec<Option<usize>> {
+    let mut detectors = lens.iter().map(|&n| Detector::new(n)).collect::<Vec<_>>();
+    let mut found = vec![None; lens.len()];
+    for (i, b) in l.bytes().enumerate() {
+        for (d, f) in detectors.iter_mut().zip(found.iter_mut()) {
+            if d.push(b) && f.is_none() {
+                *f = Some(i + 1);
+            }
+        }
+        if found.iter().all(|f| f.is_some()) {
+            break;
+        }
+    }
+    found
+}
+
+/// Summary of a stream scan for one marker length.
+struct Found {
+    len: usize,
+    first: Option<u64>,
+    count: u64,
+}
+
+/// Scans `r` in fixed-size chunks, calling `on_marker(len, offset)` for every
+/// position at which a marker of one of the lengths `lens` ends. `offset` is
+/// the number of bytes read up to and including the marker.
+fn scan<R: Read>(
+    mut r: R,
+    lens: &[usize],
+    mut on_marker: impl FnMut(usize, u64) -> io::Result<()>,
+) -> io::Result<Vec<Found>> {
+    let mut detectors = lens.iter().map(|&n| Detector::new(n)).collect::<Vec<_>>();
+    let mut found = lens
+        .iter()
+        .map(|&len| Found {
+            len,
+            first: None,
+            count: 0,
+        })
+        .collect::<Vec<_>>();
+    let mut buf = vec![0; 1 << 16];
+    let mut offset = 0u64;
+    loop {
+        let n = match r.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &b in buf[..n].iter() {
+            offset += 1;
+            for (d, f) in detectors.iter_mut().zip(found.iter_mut()) {
+                if d.push(b) {
+                    f.first.get_or_insert(offset);
+                    f.count += 1;
+                    on_marker(f.len, offset)?;
+                }
+            }
+        }
+    }
+    Ok(found)
 }
 
 fn main() -> io::Result<()> {
+    let mut lens = vec![4, 14];
+    let mut stream = false;
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: d06 [--stream] [--markers N,N,...]",
+        )
+    };
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--stream" => stream = true,
+            "--markers" => {
+                lens = args
+                    .next()
+                    .ok_or_else(usage)?
+                    .split(',')
+                    .map(|n| n.parse::<usize>().ok().filter(|&n| n > 0))
+                    .collect::<Option<_>>()
+                    .ok_or_else(usage)?;
+            }
+            _ => return Err(usage()),
+        }
+    }
+
     let stdin = io::stdin();
+    if stream {
+        let mut out = BufWriter::new(io::stdout().lock());
+        let found = scan(stdin.lock(), &lens, |n, offset| {
+            writeln!(out, "marker {} at {}", n, offset)
+        })?;
+        for f in found {
+            match f.first {
+                Some(first) => writeln!(
+                    out,
+                    "marker {}: {} occurrences, first at {}",
+                    f.len, f.count, first
+                )?,
+                None => writeln!(out, "marker {}: not found", f.len)?,
+            }
+        }
+        return Ok(());
+    }
+
     for l in stdin.lines() {
-        println!("{}", parse_line(&l.unwrap()));
+        let found = parse_line(&l?, &lens);
+        for (n, f) in lens.iter().zip(found) {
+            match f {
+                Some(i) => println!("marker {}: {}", n, i),
+                None => println!("marker {}: not found", n),
+            }
+        }
     }
     Ok(())
 }
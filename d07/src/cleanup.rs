@@ -0,0 +1,106 @@
+//! Choosing directories to delete to free a given amount of space.
+
+use crate::tree::{Tree, ROOT};
+
+/// The smallest single directory of at least `needed` bytes.
+pub fn smallest_single(sizes: &[usize], needed: usize) -> Option<usize> {
+    (0..sizes.len())
+        .filter(|&d| sizes[d] >= needed)
+        .min_by_key(|&d| sizes[d])
+}
+
+/// Directories in pre-order, larger subdirectories first, with the position
+/// just past each one's subtree.
+fn preorder(tree: &Tree, sizes: &[usize], d: usize, order: &mut Vec<usize>, end: &mut Vec<usize>) {
+    let i = order.len();
+    order.push(d);
+    end.push(0);
+    let mut subs = tree.dirs[d].dirs.values().copied().collect::<Vec<_>>();
+    subs.sort_by_key(|&sub| std::cmp::Reverse(sizes[sub]));
+    for sub in subs {
+        preorder(tree, sizes, sub, order, end);
+    }
+    end[i] = order.len();
+}
+
+/// Branch and bound over the directories in pre-order: each one is either
+/// deleted, which skips its whole subtree, or kept, which moves on to its
+/// first child. Only the current path is kept in memory, whatever the byte
+/// sizes, but like any subset sum the time can grow exponentially with the
+/// number of directories.
+struct Search<'a> {
+    order: Vec<usize>,
+    end: Vec<usize>,
+    sizes: &'a [usize],
+    needed: usize,
+    /// Most that can still be deleted from position `i` on: the directories
+    /// there that are not inside another one from there on.
+    rest: Vec<usize>,
+    /// Smallest non-empty directory from position `i` on.
+    least: Vec<usize>,
+    chosen: Vec<usize>,
+    best: Option<(usize, Vec<usize>)>,
+}
+
+impl Search<'_> {
+    fn run(&mut self, i: usize, total: usize) {
+        if total >= self.needed {
+            if self.best.as_ref().is_none_or(|(b, _)| total < *b) {
+                self.best = Some((total, self.chosen.clone()));
+            }
+            return;
+        }
+        let bound = self.best.as_ref().map_or(usize::MAX, |(b, _)| *b);
+        if i == self.order.len()
+            || total + self.rest[i] < self.needed
+            || total.saturating_add(self.least[i]) >= bound
+            || bound == self.needed
+        {
+            return;
+        }
+        let d = self.order[i];
+        let size = self.sizes[d];
+        if size > 0 && total + size < bound {
+            self.chosen.push(d);
+            self.run(self.end[i], total + size);
+            self.chosen.pop();
+        }
+        self.run(i + 1, total);
+    }
+}
+
+/// The set of directories, none inside another, that frees at least
+/// `needed` bytes while deleting as little as possible.
+pub fn smallest_set(tree: &Tree, sizes: &[usize], needed: usize) -> Option<Vec<usize>> {
+    if needed == 0 {
+        return Some(Vec::new());
+    }
+    let mut order = Vec::new();
+    let mut end = Vec::new();
+    preorder(tree, sizes, ROOT, &mut order, &mut end);
+    let mut rest = vec![0; order.len() + 1];
+    let mut least = vec![usize::MAX; order.len() + 1];
+    for i in (0..order.len()).rev() {
+        let size = sizes[order[i]];
+        rest[i] = size + rest[end[i]];
+        least[i] = if size > 0 {
+            least[i + 1].min(size)
+        } else {
+            least[i + 1]
+        };
+    }
+    let mut s = Search {
+        order,
+        end,
+        sizes,
+        needed,
+        rest,
+        least,
+        chosen: Vec::new(),
+        best: None,
+    };
+    s.run(0, 0);
+    let (_, mut chosen) = s.best?;
+    chosen.sort_by_key(|&d| tree.path(d));
+    Some(chosen)
+}
@@ -0,0 +1,70 @@
+//! Writing a reconstructed tree out as JSON or as a shell script.
+
+use crate::tree::{bad_name, Tree, ROOT};
+
+fn json_string(s: &str) -> String {
+    let mut r = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => r.push_str("\\\""),
+            '\\' => r.push_str("\\\\"),
+            c if (c as u32) < 0x20 => r.push_str(&format!("\\u{:04x}", c as u32)),
+            c => r.push(c),
+        }
+    }
+    r.push('"');
+    r
+}
+
+/// `d` as a JSON object with its name, total size, subdirectories and files.
+pub fn json(tree: &Tree, sizes: &[usize], d: usize) -> String {
+    let dir = &tree.dirs[d];
+    let name = if d == ROOT { "/" } else { &dir.name };
+    let dirs = dir
+        .dirs
+        .values()
+        .map(|&sub| json(tree, sizes, sub))
+        .collect::<Vec<_>>();
+    let files = dir
+        .files
+        .iter()
+        .map(|(n, s)| format!("{{\"name\":{},\"size\":{}}}", json_string(n), s))
+        .collect::<Vec<_>>();
+    format!(
+        "{{\"name\":{},\"size\":{},\"dirs\":[{}],\"files\":[{}]}}",
+        json_string(name),
+        sizes[d],
+        dirs.join(","),
+        files.join(",")
+    )
+}
+
+fn quote(path: &str) -> String {
+    format!("'.{}'", path.replace('\'', "'\\''"))
+}
+
+/// A shell script that recreates the tree below the current directory,
+/// using sparse files of the right sizes. Refuses names that could reach
+/// outside of it.
+pub fn script(tree: &Tree) -> Result<String, String> {
+    let mut r = String::from("#!/bin/sh\nset -e\n");
+    for d in 0..tree.dirs.len() {
+        let names = tree.dirs[d].files.keys().map(|n| n.as_str());
+        let names = names.chain((d != ROOT).then_some(tree.dirs[d].name.as_str()));
+        if let Some(e) = names.filter_map(bad_name).next() {
+            return Err(e);
+        }
+        let path = match d {
+            ROOT => String::new(),
+            _ => tree.path(d),
+        };
+        if d != ROOT {
+            r.push_str(&format!("mkdir -p {}\n", quote(&path)));
+        }
+        for (name, size) in tree.dirs[d].files.iter() {
+            let file = format!("{}/{}", path, name);
+            r.push_str(&format!("truncate -s {} {}\n", size, quote(&file)));
+        }
+    }
+    Ok(r)
+}
@@ -1,74 +1,151 @@
-use std::collections::{HashMap, VecDeque};
+mod cleanup;
+mod export;
+mod tree;
+
+use std::env;
 use std::io;
+use tree::{Tree, ROOT};
 
 enum Line {
+    CdRoot,
     Cd(String),
     CdDotDot,
-    FileSize(usize),
+    Ls,
+    Dir(String),
+    File(String, usize),
 }
 
-fn parse_line(l: &str) -> Option<Line> {
-    let mut parts = l.split(" ");
-    let first = parts.next().unwrap();
-    match first {
-        "$" => match parts.next().unwrap() {
-            "ls" => None,
-            "cd" => match parts.next().unwrap() {
-                ".." => Some(Line::CdDotDot),
-                x => Some(Line::Cd(x.to_string())),
-            },
-            x => panic!("unknown command: {}", x),
+fn parse_line(l: &str) -> Result<Line, String> {
+    let parts = l.split(' ').collect::<Vec<_>>();
+    match parts.as_slice() {
+        ["$", "ls"] => Ok(Line::Ls),
+        ["$", "cd", "/"] => Ok(Line::CdRoot),
+        ["$", "cd", ".."] => Ok(Line::CdDotDot),
+        ["$", "cd", x] => Ok(Line::Cd(x.to_string())),
+        ["$", x, ..] => Err(format!("unknown command: {}", x)),
+        ["dir", x] => Ok(Line::Dir(x.to_string())),
+        [size, x] => match size.parse::<usize>() {
+            Ok(size) => Ok(Line::File(x.to_string(), size)),
+            Err(_) => Err(format!("bad line: {:?}", l)),
         },
-        "dir" => None,
-        _ => Some(Line::FileSize(first.parse::<usize>().unwrap())),
+        _ => Err(format!("bad line: {:?}", l)),
     }
 }
 
-fn add_sizes(dir_sizes: &mut HashMap<String, usize>, path: &Vec<String>, size: usize) {
-    let mut p = String::new();
-    for x in path {
-        p = format!("{}/{}", p, x);
-        let path_entry = dir_sizes.entry(p.clone()).or_default();
-        *path_entry += size;
+/// Prints `d` and everything below it like `tree`, largest entries first.
+fn report(tree: &Tree, sizes: &[usize], d: usize, depth: usize) {
+    let dir = &tree.dirs[d];
+    let name = if d == ROOT { "/" } else { &dir.name };
+    println!("{:>10}  {}{}", sizes[d], "  ".repeat(depth), name);
+    let mut entries = dir
+        .dirs
+        .values()
+        .map(|&sub| (sizes[sub], Some(sub), ""))
+        .chain(dir.files.iter().map(|(n, s)| (*s, None, n.as_str())))
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.0));
+    for (size, sub, name) in entries {
+        match sub {
+            Some(sub) => report(tree, sizes, sub, depth + 1),
+            None => println!("{:>10}  {}{}", size, "  ".repeat(depth + 1), name),
+        }
     }
 }
 
 fn main() -> io::Result<()> {
+    let mut disk = 70000000;
+    let mut required = 30000000;
+    let mut threshold = 100000;
+    let mut mode = None;
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        if matches!(a.as_str(), "check" | "json" | "sh") {
+            mode = Some(a);
+            continue;
+        }
+        let value = args.next().and_then(|n| n.parse::<usize>().ok());
+        match (a.as_str(), value) {
+            ("--disk", Some(v)) => disk = v,
+            ("--required", Some(v)) => required = v,
+            ("--threshold", Some(v)) => threshold = v,
+            _ => {
+                let e = "usage: d07 [check|json|sh] [--disk N] [--required N] [--threshold N]";
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, e));
+            }
+        }
+    }
+
     let stdin = io::stdin();
 
-    let mut dir_sizes: HashMap<String, usize> = HashMap::new();
-    let mut path: Vec<String> = Vec::new();
-    for l in stdin.lines() {
-        if let Some(c) = parse_line(&l.unwrap()) {
-            match c {
-                Line::Cd(x) => path.push(x),
-                Line::CdDotDot => {
-                    path.pop();
-                }
-                Line::FileSize(s) => add_sizes(&mut dir_sizes, &path, s),
+    let mut tree = Tree::new();
+    let mut problems = Vec::new();
+    for (n, l) in stdin.lines().enumerate() {
+        let problem = match parse_line(&l?) {
+            Ok(Line::CdRoot) => {
+                tree.cd_root();
+                None
             }
+            Ok(Line::Cd(x)) => tree.cd(&x),
+            Ok(Line::CdDotDot) => tree.cd_up(),
+            Ok(Line::Ls) => None,
+            Ok(Line::Dir(x)) => tree.add_dir(&x),
+            Ok(Line::File(x, s)) => tree.add_file(&x, s),
+            Err(e) => Some(e),
+        };
+        if let Some(p) = problem {
+            problems.push(format!("line {}: {}", n + 1, p));
         }
     }
-    println!("dir_sizes: {:?}", dir_sizes);
-    let mut sum_100000 = 0;
-    for s in dir_sizes.values() {
-        if *s < 100000 {
-            sum_100000 += s;
+    let sizes = tree.sizes();
+
+    match mode.as_deref() {
+        Some("check") => {
+            for p in problems.iter() {
+                println!("{}", p);
+            }
+            println!("{} problems", problems.len());
+            return Ok(());
+        }
+        Some("json") => {
+            println!("{}", export::json(&tree, &sizes, ROOT));
+            return Ok(());
+        }
+        Some("sh") => {
+            let script =
+                export::script(&tree).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            print!("{}", script);
+            return Ok(());
         }
+        _ => {}
     }
-    println!("sum_100000: {}", sum_100000);
+    for p in problems.iter() {
+        eprintln!("warning: {}", p);
+    }
+    report(&tree, &sizes, ROOT, 0);
+    println!();
+
+    let small: usize = sizes.iter().filter(|s| **s < threshold).sum();
+    println!("sum below {}: {}", threshold, small);
 
-    let total: usize = *dir_sizes.get("//").unwrap();
-    let free = 70000000 - total;
-    let required = 30000000 - free;
-    println!("required: {}", required);
-    let mut min_size = total;
-    for s in dir_sizes.values() {
-        if *s >= required && *s < min_size {
-            min_size = *s;
+    let total = sizes[ROOT];
+    let Some(free) = disk.checked_sub(total) else {
+        let e = format!("{} bytes used on a disk of {}", total, disk);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+    };
+    let needed = required.saturating_sub(free);
+    println!("used: {} free: {} needed: {}", total, free, needed);
+    match cleanup::smallest_single(&sizes, needed) {
+        Some(d) => println!("smallest single: {} ({})", tree.path(d), sizes[d]),
+        None => println!("smallest single: none"),
+    }
+    match cleanup::smallest_set(&tree, &sizes, needed) {
+        Some(set) => {
+            let freed: usize = set.iter().map(|&d| sizes[d]).sum();
+            let paths = set.iter().map(|&d| tree.path(d)).collect::<Vec<_>>();
+            println!("smallest set: {} ({})", paths.join(" "), freed);
         }
+        None => println!("smallest set: none"),
     }
-    println!("min_size: {}", min_size);
 
     Ok(())
 }
@@ -0,0 +1,170 @@
+//! In-memory filesystem rebuilt from a terminal transcript.
+
+use std::collections::BTreeMap;
+
+pub struct Dir {
+    pub name: String,
+    pub parent: Option<usize>,
+    /// Whether the directory showed up as a `dir` entry in a listing.
+    pub listed: bool,
+    pub dirs: BTreeMap<String, usize>,
+    pub files: BTreeMap<String, usize>,
+}
+
+/// Directories are kept in an arena and refer to each other by index. The
+/// root is always index 0.
+///
+/// Commands that do not fit what is already known are still carried out as
+/// well as possible, and return a description of the problem.
+pub struct Tree {
+    pub dirs: Vec<Dir>,
+    cwd: usize,
+}
+
+pub const ROOT: usize = 0;
+
+/// Why `name` cannot be an entry of a directory, if it cannot.
+pub fn bad_name(name: &str) -> Option<String> {
+    match name {
+        "" | "." | ".." => Some(format!("invalid name {:?}", name)),
+        _ if name.contains('/') => Some(format!("name with a slash {:?}", name)),
+        _ => None,
+    }
+}
+
+impl Tree {
+    pub fn new() -> Self {
+        Tree {
+            dirs: vec![Dir {
+                name: String::new(),
+                parent: None,
+                listed: true,
+                dirs: BTreeMap::new(),
+                files: BTreeMap::new(),
+            }],
+            cwd: ROOT,
+        }
+    }
+
+    /// The subdirectory `name` of `parent`, created if it is not known yet.
+    fn subdir(&mut self, parent: usize, name: &str) -> usize {
+        if let Some(&d) = self.dirs[parent].dirs.get(name) {
+            return d;
+        }
+        let d = self.dirs.len();
+        self.dirs.push(Dir {
+            name: name.to_string(),
+            parent: Some(parent),
+            listed: false,
+            dirs: BTreeMap::new(),
+            files: BTreeMap::new(),
+        });
+        self.dirs[parent].dirs.insert(name.to_string(), d);
+        d
+    }
+
+    pub fn cd_root(&mut self) {
+        self.cwd = ROOT;
+    }
+
+    /// Moves to the parent directory; staying put at the root like a shell.
+    pub fn cd_up(&mut self) -> Option<String> {
+        match self.dirs[self.cwd].parent {
+            Some(p) => {
+                self.cwd = p;
+                None
+            }
+            None => Some("cd .. at /".to_string()),
+        }
+    }
+
+    /// Moves into the subdirectory `name`. Invalid names are ignored.
+    pub fn cd(&mut self, name: &str) -> Option<String> {
+        if let Some(e) = bad_name(name) {
+            return Some(format!("cd ignored: {}", e));
+        }
+        if self.dirs[self.cwd].files.contains_key(name) {
+            return Some(format!("cd into file {}", self.entry_path(name)));
+        }
+        self.cwd = self.subdir(self.cwd, name);
+        match self.dirs[self.cwd].listed {
+            true => None,
+            false => Some(format!(
+                "cd into unlisted directory {}",
+                self.path(self.cwd)
+            )),
+        }
+    }
+
+    pub fn add_dir(&mut self, name: &str) -> Option<String> {
+        if let Some(e) = bad_name(name) {
+            return Some(format!("directory ignored: {}", e));
+        }
+        if self.dirs[self.cwd].files.contains_key(name) {
+            return Some(format!(
+                "{} listed as file and directory",
+                self.entry_path(name)
+            ));
+        }
+        let d = self.subdir(self.cwd, name);
+        self.dirs[d].listed = true;
+        None
+    }
+
+    /// Records a file in the current directory. Listing a directory again
+    /// overwrites its entries instead of adding to them. Invalid names are
+    /// ignored.
+    pub fn add_file(&mut self, name: &str, size: usize) -> Option<String> {
+        if let Some(e) = bad_name(name) {
+            return Some(format!("file ignored: {}", e));
+        }
+        if self.dirs[self.cwd].dirs.contains_key(name) {
+            return Some(format!(
+                "{} listed as file and directory",
+                self.entry_path(name)
+            ));
+        }
+        match self.dirs[self.cwd].files.insert(name.to_string(), size) {
+            Some(old) if old != size => Some(format!(
+                "{} listed with sizes {} and {}",
+                self.entry_path(name),
+                old,
+                size
+            )),
+            _ => None,
+        }
+    }
+
+    /// Path of the entry `name` in the current directory.
+    fn entry_path(&self, name: &str) -> String {
+        match self.cwd {
+            ROOT => format!("/{}", name),
+            d => format!("{}/{}", self.path(d), name),
+        }
+    }
+
+    /// Total size of every directory, including everything below it.
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.dirs.len()];
+        self.add_sizes(ROOT, &mut sizes);
+        sizes
+    }
+
+    fn add_sizes(&self, d: usize, sizes: &mut [usize]) -> usize {
+        let dir = &self.dirs[d];
+        let mut size = dir.files.values().sum();
+        for &sub in dir.dirs.values() {
+            size += self.add_sizes(sub, sizes);
+        }
+        sizes[d] = size;
+        size
+    }
+
+    pub fn path(&self, d: usize) -> String {
+        match self.dirs[d].parent {
+            None => "/".to_string(),
+            Some(ROOT) => format!("/{}", self.dirs[d].name),
+            Some(p) => format!("{}/{}", self.path(p), self.dirs[d].name),
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Reading height maps in the puzzle's digit format, as whitespace-separated
+//! numbers or as CSV.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Anything trees can be measured in.
+pub trait Height: Copy + Ord + Display + Into<i64> {}
+
+impl<T: Copy + Ord + Display + Into<i64>> Height for T {}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    /// One digit per tree, as in the puzzle input.
+    Digits,
+    Whitespace,
+    Csv,
+}
+
+impl Format {
+    /// Guesses the format. Digits are only assumed when every line is a run
+    /// of digits of the same length; anything else without commas is read
+    /// as whitespace-separated numbers, one column or more.
+    pub fn detect(lines: &[String]) -> Format {
+        let lines = lines
+            .iter()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>();
+        let digits = lines.iter().all(|l| l.bytes().all(|b| b.is_ascii_digit()));
+        let width = lines.first().map_or(0, |l| l.len());
+        if lines.iter().any(|l| l.contains(',')) {
+            Format::Csv
+        } else if digits && lines.iter().all(|l| l.len() == width) {
+            Format::Digits
+        } else {
+            Format::Whitespace
+        }
+    }
+
+    /// Whether `lines` detected as digits could just as well be a single
+    /// column of numbers.
+    pub fn could_be_column(lines: &[String]) -> bool {
+        lines.iter().all(|l| l.trim().len() <= 18)
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "digits" => Ok(Format::Digits),
+            "ws" => Ok(Format::Whitespace),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "unknown format {:?}, expected digits, ws or csv",
+                s
+            )),
+        }
+    }
+}
+
+fn check_shape<T>(grid: Vec<Vec<T>>) -> Result<Vec<Vec<T>>, String> {
+    let Some(w) = grid.first().map(|r| r.len()) else {
+        return Err("empty height map".to_string());
+    };
+    if w == 0 {
+        return Err("empty height map".to_string());
+    }
+    for (y, row) in grid.iter().enumerate() {
+        if row.len() != w {
+            return Err(format!(
+                "line {}: {} trees, expected {}",
+                y + 1,
+                row.len(),
+                w
+            ));
+        }
+    }
+    Ok(grid)
+}
+
+pub fn parse_digits(lines: &[String]) -> Result<Vec<Vec<i8>>, String> {
+    let grid = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !l.is_empty())
+        .map(|(y, l)| {
+            l.chars()
+                .enumerate()
+                .map(|(x, c)| match c.to_digit(10) {
+                    Some(d) => Ok(d as i8),
+                    None => Err(format!(
+                        "line {} column {}: {:?} is not a digit",
+                        y + 1,
+                        x + 1,
+                        c
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    check_shape(grid)
+}
+
+pub fn parse_numbers(lines: &[String], format: Format) -> Result<Vec<Vec<i64>>, String> {
+    let grid = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(y, l)| {
+            let fields: Vec<&str> = match format {
+                Format::Csv => l.split(',').map(|f| f.trim()).collect(),
+                _ => l.split_whitespace().collect(),
+            };
+            fields
+                .iter()
+                .enumerate()
+                .map(|(x, f)| {
+                    f.parse::<i64>().map_err(|_| {
+                        format!("line {} field {}: {:?} is not a height", y + 1, x + 1, f)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    check_shape(grid)
+}
@@ -1,111 +1,254 @@
-use std::cmp::max;
-use std::collections::HashSet;
+mod heightmap;
+mod render;
+
+use heightmap::{Format, Height};
+use std::env;
+use std::fs;
 use std::io;
 
-fn parse_line(l: &str) -> Vec<i8> {
-    l.chars().map(|c| c as i8 - '0' as i8).collect()
+/// Dense `w`×`h` bitmap, row by row.
+struct Bitmap {
+    w: usize,
+    bits: Vec<u64>,
+}
+
+impl Bitmap {
+    fn new(w: usize, h: usize) -> Self {
+        Bitmap {
+            w,
+            bits: vec![0; (w * h).div_ceil(64)],
+        }
+    }
+
+    fn set(&mut self, y: usize, x: usize) {
+        let i = y * self.w + x;
+        self.bits[i / 64] |= 1 << (i % 64);
+    }
+
+    fn get(&self, y: usize, x: usize) -> bool {
+        let i = y * self.w + x;
+        self.bits[i / 64] & 1 << (i % 64) != 0
+    }
+
+    fn count(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
 }
 
-fn get_vismap(grid: &Vec<Vec<i8>>) -> HashSet<(usize, usize)> {
+fn get_vismap<T: Height>(grid: &[Vec<T>]) -> Bitmap {
     let h = grid.len();
     let w = grid[0].len();
-    let mut v = HashSet::new();
-    for y in 0..h {
-        let mut t = -1;
-        for x in 0..w {
-            if grid[y][x] > t {
-                v.insert((y, x));
+    let mut v = Bitmap::new(w, h);
+    for (y, row) in grid.iter().enumerate() {
+        let mut t = None;
+        for (x, &tree) in row.iter().enumerate() {
+            if t.is_none_or(|t| tree > t) {
+                v.set(y, x);
+                t = Some(tree);
             }
-            t = max(t, grid[y][x]);
         }
-        t = -1;
-        for x in (0..w).rev() {
-            if grid[y][x] > t {
-                v.insert((y, x));
+        t = None;
+        for (x, &tree) in row.iter().enumerate().rev() {
+            if t.is_none_or(|t| tree > t) {
+                v.set(y, x);
+                t = Some(tree);
             }
-            t = max(t, grid[y][x]);
         }
     }
 
     for x in 0..w {
-        let mut t = -1;
-        for y in 0..h {
-            if grid[y][x] > t {
-                v.insert((y, x));
+        let mut t = None;
+        for (y, row) in grid.iter().enumerate() {
+            if t.is_none_or(|t| row[x] > t) {
+                v.set(y, x);
+                t = Some(row[x]);
             }
-            t = max(t, grid[y][x]);
         }
-        t = -1;
-        for y in (0..h).rev() {
-            if grid[y][x] > t {
-                v.insert((y, x));
+        t = None;
+        for (y, row) in grid.iter().enumerate().rev() {
+            if t.is_none_or(|t| row[x] > t) {
+                v.set(y, x);
+                t = Some(row[x]);
             }
-            t = max(t, grid[y][x]);
         }
     }
     v
 }
 
-fn get_scenic_score(grid: &Vec<Vec<i8>>) -> i32 {
+/// Viewing distance towards the start of `line` from every tree in it. A
+/// stack keeps the trees that can still block a view, tallest at the
+/// bottom, so each tree is pushed and popped once.
+fn view_distances<T: Height>(line: &[T]) -> Vec<u64> {
+    let mut stack: Vec<usize> = Vec::new();
+    let mut d = Vec::with_capacity(line.len());
+    for (i, &tree) in line.iter().enumerate() {
+        while stack.last().is_some_and(|&j| line[j] < tree) {
+            stack.pop();
+        }
+        d.push(match stack.last() {
+            Some(&j) => (i - j) as u64,
+            None => i as u64,
+        });
+        stack.push(i);
+    }
+    d
+}
+
+/// Scenic score of every tree: the product of its viewing distances in all
+/// four directions.
+fn get_scenic_scores<T: Height>(grid: &[Vec<T>]) -> Vec<Vec<u64>> {
     let h = grid.len();
     let w = grid[0].len();
-    let mut m = 0;
-
+    let mut scores = vec![vec![1; w]; h];
+    let mut apply = |cells: &[(usize, usize)]| {
+        let line = cells.iter().map(|&(y, x)| grid[y][x]).collect::<Vec<_>>();
+        for (&(y, x), d) in cells.iter().zip(view_distances(&line)) {
+            scores[y][x] *= d;
+        }
+    };
     for y in 0..h {
-        for x in 0..w {
-            let mut score = 1;
-            let mut ascore = 0;
-            for ax in (0..x).rev() {
-                ascore += 1;
-                if grid[y][ax] >= grid[y][x] {
-                    break;
-                }
-            }
-            score *= ascore;
-
-            ascore = 0;
-            for ax in x + 1..w {
-                ascore += 1;
-                if grid[y][ax] >= grid[y][x] {
-                    break;
-                }
-            }
-            score *= ascore;
-
-            ascore = 0;
-            for ay in (0..y).rev() {
-                ascore += 1;
-                if grid[ay][x] >= grid[y][x] {
-                    break;
-                }
+        let mut cells = (0..w).map(|x| (y, x)).collect::<Vec<_>>();
+        apply(&cells);
+        cells.reverse();
+        apply(&cells);
+    }
+    for x in 0..w {
+        let mut cells = (0..h).map(|y| (y, x)).collect::<Vec<_>>();
+        apply(&cells);
+        cells.reverse();
+        apply(&cells);
+    }
+    scores
+}
+
+fn get_scenic_score<T: Height>(grid: &[Vec<T>]) -> u64 {
+    get_scenic_scores(grid)
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or(0)
+}
+
+const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Trees visible from an eye at height `eye` above the ground at `(oy, ox)`,
+/// looking along the 8 compass directions. A tree is hidden if a tree in
+/// between reaches the straight line from the eye to its top.
+fn get_observer_vismap<T: Height>(grid: &[Vec<T>], oy: usize, ox: usize, eye: i64) -> Bitmap {
+    let h = grid.len() as isize;
+    let w = grid[0].len() as isize;
+    let mut v = Bitmap::new(w as usize, h as usize);
+    for (dy, dx) in DIRECTIONS {
+        let mut ray = Vec::new();
+        let (mut y, mut x) = (oy as isize + dy, ox as isize + dx);
+        while (0..h).contains(&y) && (0..w).contains(&x) {
+            ray.push((y as usize, x as usize));
+            y += dy;
+            x += dx;
+        }
+        for (k, &(ty, tx)) in ray.iter().enumerate() {
+            let height = |y: usize, x: usize| i128::from(grid[y][x].into());
+            let (k, t, eye) = (k as i128 + 1, height(ty, tx), i128::from(eye));
+            // Compare heights scaled by `k` to stay in integers, and in i128
+            // so that no height can overflow.
+            let hidden = ray[..k as usize - 1]
+                .iter()
+                .enumerate()
+                .any(|(i, &(y, x))| height(y, x) * k >= eye * k + (t - eye) * (i as i128 + 1));
+            if !hidden {
+                v.set(ty, tx);
             }
-            score *= ascore;
-
-            ascore = 0;
-            for ay in y + 1..h {
-                ascore += 1;
-                if grid[ay][x] >= grid[y][x] {
-                    break;
-                }
+        }
+    }
+    v
+}
+
+fn run<T: Height>(grid: &[Vec<T>], args: &[&str]) -> io::Result<()> {
+    let v = get_vismap(grid);
+
+    match args {
+        ["heatmap"] => {
+            print!(
+                "{}",
+                render::terminal(grid, &get_scenic_scores(grid), |y, x| v.get(y, x))
+            );
+            return Ok(());
+        }
+        ["image", path] => {
+            let image = render::ppm(grid, &get_scenic_scores(grid), |y, x| v.get(y, x));
+            return fs::write(path, image);
+        }
+        ["observe", x, y, eye] => {
+            let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidInput, e);
+            let parse = |s: &str| s.parse::<i64>().map_err(|e| invalid(e.to_string()));
+            let (x, y, eye) = (parse(x)?, parse(y)?, parse(eye)?);
+            if !(0..grid.len() as i64).contains(&y) || !(0..grid[0].len() as i64).contains(&x) {
+                return Err(invalid("observer outside the forest".to_string()));
             }
-            score *= ascore;
-            m = max(m, score);
+            let (x, y) = (x as usize, y as usize);
+            let o = get_observer_vismap(grid, y, x, eye);
+            print!("{}", render::observer(grid, (y, x), |y, x| o.get(y, x)));
+            println!(
+                "visible from ({}, {}) at height {}: {}",
+                x,
+                y,
+                eye,
+                o.count()
+            );
+            return Ok(());
+        }
+        [] => {}
+        _ => {
+            let e = "usage: d08 [--format digits|ws|csv] [heatmap | image PATH | observe X Y H]";
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, e));
         }
     }
-    m
+
+    println!("num_visible: {}", v.count());
+    println!("scenic_score: {}", get_scenic_score(grid));
+
+    Ok(())
 }
 
 fn main() -> io::Result<()> {
     let stdin = io::stdin();
+    let lines = stdin.lines().collect::<io::Result<Vec<_>>>()?;
 
-    let grid = stdin
-        .lines()
-        .map(|l| parse_line(&l.unwrap()))
-        .collect::<Vec<_>>();
-    let v = get_vismap(&grid);
-
-    println!("num_visible: {}", v.len());
-    println!("scenic_score: {}", get_scenic_score(&grid));
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let mut args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+    let format = match args.iter().position(|a| *a == "--format") {
+        Some(i) => {
+            let f = args.get(i + 1).copied().unwrap_or_default();
+            args.drain(i..(i + 2).min(args.len()));
+            f.parse::<Format>().map_err(invalid)?
+        }
+        None => {
+            let f = Format::detect(&lines);
+            if f == Format::Digits && Format::could_be_column(&lines) {
+                eprintln!(
+                    "note: reading one digit per tree, pass --format ws for one number per line"
+                );
+            }
+            f
+        }
+    };
 
-    Ok(())
+    match format {
+        Format::Digits => run(&heightmap::parse_digits(&lines).map_err(invalid)?, &args),
+        _ => run(
+            &heightmap::parse_numbers(&lines, format).map_err(invalid)?,
+            &args,
+        ),
+    }
 }
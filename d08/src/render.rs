@@ -0,0 +1,112 @@
+//! Heatmaps of scenic scores, for the terminal and as PPM images.
+
+use std::fmt::Display;
+
+/// Pixels per tree in images.
+const SCALE: usize = 4;
+
+/// Blue through yellow to red for `t` in `0.0..=1.0`.
+fn colour(t: f64) -> (u8, u8, u8) {
+    let t = t.clamp(0.0, 1.0);
+    let (r, g, b) = if t < 0.5 {
+        (t * 2.0, t * 2.0, 1.0 - t * 2.0)
+    } else {
+        (1.0, 2.0 - t * 2.0, 0.0)
+    };
+    ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+}
+
+/// Colours on a log scale, since a few trees have far higher scores than
+/// the rest. Trees hidden from every edge are darkened.
+fn cell_colour(score: u64, max: u64, visible: bool) -> (u8, u8, u8) {
+    let t = (score as f64).ln_1p() / (max as f64).ln_1p().max(1.0);
+    let (r, g, b) = colour(t);
+    match visible {
+        true => (r, g, b),
+        false => (r / 3, g / 3, b / 3),
+    }
+}
+
+/// Widest height in `grid`, so columns line up. Wider heights also get a
+/// blank between columns.
+fn cell_width<T: Display>(grid: &[Vec<T>]) -> usize {
+    grid.iter()
+        .flatten()
+        .map(|t| t.to_string().len())
+        .max()
+        .unwrap_or(1)
+}
+
+pub fn terminal<T: Display>(
+    grid: &[Vec<T>],
+    scores: &[Vec<u64>],
+    visible: impl Fn(usize, usize) -> bool,
+) -> String {
+    let max = scores.iter().flatten().copied().max().unwrap_or(0);
+    let width = cell_width(grid);
+    let mut out = String::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, tree) in row.iter().enumerate() {
+            if x > 0 && width > 1 {
+                out.push(' ');
+            }
+            let (r, g, b) = cell_colour(scores[y][x], max, visible(y, x));
+            out.push_str(&format!(
+                "\x1b[48;2;{};{};{}m\x1b[30m{:>width$}",
+                r, g, b, tree
+            ));
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// A binary PPM image with `SCALE`×`SCALE` pixels per tree.
+pub fn ppm<T>(
+    grid: &[Vec<T>],
+    scores: &[Vec<u64>],
+    visible: impl Fn(usize, usize) -> bool,
+) -> Vec<u8> {
+    let max = scores.iter().flatten().copied().max().unwrap_or(0);
+    let (h, w) = (grid.len(), grid[0].len());
+    let mut out = format!("P6\n{} {}\n255\n", w * SCALE, h * SCALE).into_bytes();
+    for y in 0..h * SCALE {
+        for x in 0..w * SCALE {
+            let (r, g, b) = cell_colour(
+                scores[y / SCALE][x / SCALE],
+                max,
+                visible(y / SCALE, x / SCALE),
+            );
+            out.extend([r, g, b]);
+        }
+    }
+    out
+}
+
+/// The forest with the trees an observer can see highlighted and the
+/// observer marked with `@`.
+pub fn observer<T: Display>(
+    grid: &[Vec<T>],
+    at: (usize, usize),
+    visible: impl Fn(usize, usize) -> bool,
+) -> String {
+    let width = cell_width(grid);
+    let mut out = String::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, tree) in row.iter().enumerate() {
+            if x > 0 && width > 1 {
+                out.push(' ');
+            }
+            if (y, x) == at {
+                out.push_str(&format!("\x1b[1;31m{:>width$}", "@"));
+            } else if visible(y, x) {
+                out.push_str(&format!("\x1b[1;32m{:>width$}", tree));
+            } else {
+                out.push_str(&format!("\x1b[2;37m{:>width$}", tree));
+            }
+            out.push_str("\x1b[0m");
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -1,45 +1,200 @@
-use std::cmp::max;
-use std::collections::HashSet;
+mod render;
+
+use std::collections::HashMap;
+use std::env;
 use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// A point with one coordinate per dimension. Axis 0 is rows (`U`/`D`),
+/// axis 1 columns (`L`/`R`) and axis 2 depth (`F`/`B`).
+type Pos = Vec<i32>;
 
-fn parse_line(l: &str) -> (char, i32) {
-    let d = l.chars().next().unwrap();
-    let s = l[2..].parse::<i32>().unwrap();
-    (d, s)
+/// Parses a move such as `R 4`, a diagonal like `UR 2`, a 3D move like
+/// `UFL 1`, or an explicit unit vector like `0,1,-1,1 3` for any number of
+/// dimensions. Returns the direction and the number of steps.
+fn parse_line(l: &str) -> Result<(Pos, i32), String> {
+    let (d, s) = l
+        .split_once(' ')
+        .ok_or_else(|| format!("bad move {:?}", l))?;
+    let s = s
+        .parse::<i32>()
+        .ok()
+        .filter(|s| *s >= 0)
+        .ok_or_else(|| format!("bad step count in {:?}", l))?;
+    if d.contains(|c: char| c.is_ascii_digit()) {
+        let v = d
+            .split(',')
+            .map(|x| x.parse::<i32>().ok().filter(|x| x.abs() <= 1))
+            .collect::<Option<Pos>>()
+            .ok_or_else(|| format!("bad direction vector in {:?}", l))?;
+        return Ok((v, s));
+    }
+    let mut v = vec![0; 3];
+    for c in d.chars() {
+        let (axis, delta) = match c {
+            'U' => (0, -1),
+            'D' => (0, 1),
+            'L' => (1, -1),
+            'R' => (1, 1),
+            'B' => (2, -1),
+            'F' => (2, 1),
+            x => return Err(format!("expected direction: {}", x)),
+        };
+        if v[axis] != 0 {
+            return Err(format!("axis used twice in {:?}", l));
+        }
+        v[axis] = delta;
+    }
+    // Trailing unused axes are dropped so 2D input stays 2D.
+    while v.len() > 2 && v.last() == Some(&0) {
+        v.pop();
+    }
+    Ok((v, s))
+}
+
+struct Rope {
+    knots: Vec<Pos>,
+    /// A knot only moves once its Chebyshev distance to the one ahead
+    /// exceeds this.
+    slack: i32,
+    /// How often each knot has been at each position, knot 0 being the head.
+    visits: Vec<HashMap<Pos, u32>>,
+}
+
+impl Rope {
+    fn new(len: usize, dims: usize, slack: i32) -> Self {
+        let knots = vec![vec![0; dims]; len];
+        let visits = knots
+            .iter()
+            .map(|k| HashMap::from([(k.clone(), 1)]))
+            .collect();
+        Rope {
+            knots,
+            slack,
+            visits,
+        }
+    }
+
+    fn step(&mut self, d: &[i32]) {
+        let rope = &mut self.knots;
+        for (x, d) in rope[0].iter_mut().zip(d) {
+            *x += d;
+        }
+        for i in 1..rope.len() {
+            let d = rope[i - 1]
+                .iter()
+                .zip(rope[i].iter())
+                .map(|(a, b)| a - b)
+                .collect::<Vec<_>>();
+            if d.iter().any(|x| x.abs() > self.slack) {
+                for (x, d) in rope[i].iter_mut().zip(d) {
+                    *x += d.signum();
+                }
+            }
+        }
+        for (k, v) in rope.iter().zip(self.visits.iter_mut()) {
+            *v.entry(k.clone()).or_default() += 1;
+        }
+    }
 }
 
 fn main() -> io::Result<()> {
+    let mut knots = 10;
+    let mut slack = 1;
+    let mut dims = None;
+    let mut animate = false;
+    let mut delay = None;
+    let mut trail = false;
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: d09 [--knots N] [--slack N] [--dims N] [--animate [--delay MS]] [--trail]",
+        )
+    };
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--animate" => animate = true,
+            "--trail" => trail = true,
+            "--slack" => {
+                slack = args
+                    .next()
+                    .and_then(|n| n.parse::<i32>().ok())
+                    .filter(|&s| s >= 0)
+                    .ok_or_else(usage)?
+            }
+            _ => {
+                let value = args.next().and_then(|n| n.parse::<usize>().ok());
+                match (a.as_str(), value) {
+                    ("--knots", Some(v)) if v >= 1 => knots = v,
+                    ("--dims", Some(v)) => dims = Some(v),
+                    ("--delay", Some(v)) => delay = Some(Duration::from_millis(v as u64)),
+                    _ => return Err(usage()),
+                }
+            }
+        }
+    }
+
     let stdin = io::stdin();
+    let lines = stdin.lines().collect::<io::Result<Vec<_>>>()?;
+    let moves = lines
+        .iter()
+        .map(|l| parse_line(l).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+        .collect::<io::Result<Vec<_>>>()?;
 
-    let mut rope = Vec::new();
-    rope.resize(10, (0_i32, 0_i32));
-    let mut short = HashSet::new();
-    let mut long = HashSet::new();
-
-    for l in stdin.lines() {
-        let (d, s) = parse_line(&l.unwrap());
-        println!("{} {}", d, s);
-        assert!(s >= 0);
-        for _ in 0..s {
-            match d {
-                'R' => rope[0].1 += 1,
-                'L' => rope[0].1 -= 1,
-                'U' => rope[0].0 -= 1,
-                'D' => rope[0].0 += 1,
-                x => panic!("expected direction: {}", x),
+    let needed = moves.iter().map(|(d, _)| d.len()).max().unwrap_or(2);
+    let dims = dims.unwrap_or(needed);
+    if dims < needed {
+        let e = format!("moves need {} dimensions", needed);
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, e));
+    }
+    let mut rope = Rope::new(knots, dims, slack);
+
+    // Frames are only drawn once every position is known, so that they all
+    // share the same bounds.
+    let mut frames = Vec::new();
+    for (l, (d, s)) in lines.iter().zip(moves.iter()) {
+        if !animate {
+            println!("{}", l);
+        }
+        let mut d = d.clone();
+        d.resize(dims, 0);
+        let mut steps = Vec::new();
+        for _ in 0..*s {
+            rope.step(&d);
+            if animate {
+                steps.push(rope.knots.clone());
             }
-            for i in 1..rope.len() {
-                let d = (rope[i - 1].0 - rope[i].0, rope[i - 1].1 - rope[i].1);
-                if d.0.abs() > 1 || d.1.abs() > 1 {
-                    rope[i].0 += d.0.signum();
-                    rope[i].1 += d.1.signum();
+        }
+        frames.push((l, steps));
+    }
+
+    if animate || trail {
+        let bounds = render::Bounds::new(rope.visits.iter().flat_map(|v| v.keys()));
+        if animate {
+            for (l, steps) in frames {
+                for knots in steps {
+                    match delay {
+                        Some(delay) => {
+                            print!(
+                                "\x1b[2J\x1b[H== {} ==\n\n{}",
+                                l,
+                                render::rope(&bounds, &knots)
+                            );
+                            thread::sleep(delay);
+                        }
+                        None => println!("== {} ==\n\n{}", l, render::rope(&bounds, &knots)),
+                    }
                 }
             }
-            short.insert(rope[1]);
-            long.insert(rope[rope.len() - 1]);
         }
+        if trail {
+            print!("{}", render::trail(&bounds, rope.visits.last().unwrap()));
+        }
+    }
+    for (i, v) in rope.visits.iter().enumerate() {
+        println!("knot {} visited {} positions", i, v.len());
     }
-    println!("short visited {} positions", short.len());
-    println!("long visited {} positions", long.len());
     Ok(())
 }
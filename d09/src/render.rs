@@ -0,0 +1,76 @@
+//! Drawing ropes and trails in the style of the puzzle's diagrams. Only the
+//! first two axes are drawn; higher dimensions are projected away.
+
+use crate::Pos;
+use std::collections::HashMap;
+
+/// Row and column of `p`, with a missing axis counting as 0.
+fn at(p: &Pos) -> (i32, i32) {
+    let axis = |i| p.get(i).copied().unwrap_or(0);
+    (axis(0), axis(1))
+}
+
+/// Rows and columns covered by any of `positions`, always including the start.
+pub struct Bounds {
+    rows: (i32, i32),
+    cols: (i32, i32),
+}
+
+impl Bounds {
+    pub fn new<'a>(positions: impl Iterator<Item = &'a Pos>) -> Self {
+        let mut b = Bounds {
+            rows: (0, 0),
+            cols: (0, 0),
+        };
+        for (r, c) in positions.map(at) {
+            b.rows = (b.rows.0.min(r), b.rows.1.max(r));
+            b.cols = (b.cols.0.min(c), b.cols.1.max(c));
+        }
+        b
+    }
+}
+
+/// `H` for the head, then `1`..`9` and `a`..`z` for the following knots.
+fn label(i: usize) -> char {
+    match i {
+        0 => 'H',
+        1..=9 => (b'0' + i as u8) as char,
+        _ => (b'a' + ((i - 10) % 26) as u8) as char,
+    }
+}
+
+fn draw(bounds: &Bounds, cell: impl Fn(i32, i32) -> char) -> String {
+    let mut out = String::new();
+    for r in bounds.rows.0..=bounds.rows.1 {
+        for c in bounds.cols.0..=bounds.cols.1 {
+            out.push(cell(r, c));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The rope's current shape. Knots closer to the head cover later ones and
+/// `s` marks the start.
+pub fn rope(bounds: &Bounds, knots: &[Pos]) -> String {
+    draw(bounds, |r, c| {
+        knots
+            .iter()
+            .position(|k| at(k) == (r, c))
+            .map(label)
+            .unwrap_or(if (r, c) == (0, 0) { 's' } else { '.' })
+    })
+}
+
+/// Every position in `visits` as `#`, with `s` at the start.
+pub fn trail(bounds: &Bounds, visits: &HashMap<Pos, u32>) -> String {
+    let seen = visits
+        .keys()
+        .map(at)
+        .collect::<std::collections::HashSet<_>>();
+    draw(bounds, |r, c| match (r, c) {
+        (0, 0) => 's',
+        _ if seen.contains(&(r, c)) => '#',
+        _ => '.',
+    })
+}
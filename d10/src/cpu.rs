@@ -0,0 +1,242 @@
+//! Cycle-accurate emulator for the handheld's CPU.
+
+use std::fmt;
+
+pub const REGISTERS: [char; 4] = ['x', 'y', 'z', 'w'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Registers(pub [i64; 4]);
+
+impl Registers {
+    pub fn get(&self, r: usize) -> i64 {
+        self.0[r]
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Registers([1, 0, 0, 0])
+    }
+}
+
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let regs = REGISTERS
+            .iter()
+            .zip(self.0.iter())
+            .map(|(n, v)| format!("{}={}", n, v))
+            .collect::<Vec<_>>();
+        write!(f, "{}", regs.join(" "))
+    }
+}
+
+pub fn parse_register(s: &str) -> Result<usize, String> {
+    let mut c = s.chars();
+    match (c.next(), c.next()) {
+        (Some(r), None) => REGISTERS
+            .iter()
+            .position(|n| *n == r)
+            .ok_or_else(|| format!("unknown register {}", s)),
+        _ => Err(format!("unknown register {}", s)),
+    }
+}
+
+/// An instruction takes `cycles()` cycles and changes the registers only at
+/// the very end of its last cycle.
+pub trait Instruction: fmt::Debug {
+    fn cycles(&self) -> u32;
+
+    fn execute(&self, regs: &mut Registers);
+}
+
+#[derive(Debug)]
+struct Noop;
+
+impl Instruction for Noop {
+    fn cycles(&self) -> u32 {
+        1
+    }
+
+    fn execute(&self, _: &mut Registers) {}
+}
+
+/// `addx V` and its siblings for the other registers.
+#[derive(Debug)]
+struct Add(usize, i64);
+
+impl Instruction for Add {
+    fn cycles(&self) -> u32 {
+        2
+    }
+
+    fn execute(&self, regs: &mut Registers) {
+        regs.0[self.0] += self.1;
+    }
+}
+
+/// `set R V`
+#[derive(Debug)]
+struct Set(usize, i64);
+
+impl Instruction for Set {
+    fn cycles(&self) -> u32 {
+        1
+    }
+
+    fn execute(&self, regs: &mut Registers) {
+        regs.0[self.0] = self.1;
+    }
+}
+
+/// `mul R V`
+#[derive(Debug)]
+struct Mul(usize, i64);
+
+impl Instruction for Mul {
+    fn cycles(&self) -> u32 {
+        3
+    }
+
+    fn execute(&self, regs: &mut Registers) {
+        regs.0[self.0] *= self.1;
+    }
+}
+
+/// `cpy DST SRC`
+#[derive(Debug)]
+struct Cpy(usize, usize);
+
+impl Instruction for Cpy {
+    fn cycles(&self) -> u32 {
+        1
+    }
+
+    fn execute(&self, regs: &mut Registers) {
+        regs.0[self.0] = regs.0[self.1];
+    }
+}
+
+pub fn parse_line(l: &str) -> Result<Box<dyn Instruction>, String> {
+    let parts = l.split_whitespace().collect::<Vec<_>>();
+    let num = |s: &str| s.parse::<i64>().map_err(|_| format!("bad number {}", s));
+    Ok(match parts.as_slice() {
+        ["noop"] => Box::new(Noop),
+        [op, v] if op.len() == 4 && op.starts_with("add") => {
+            Box::new(Add(parse_register(&op[3..])?, num(v)?))
+        }
+        ["set", r, v] => Box::new(Set(parse_register(r)?, num(v)?)),
+        ["mul", r, v] => Box::new(Mul(parse_register(r)?, num(v)?)),
+        ["cpy", dst, src] => Box::new(Cpy(parse_register(dst)?, parse_register(src)?)),
+        _ => return Err(format!("unknown instruction {:?}", l)),
+    })
+}
+
+/// Sees the registers during every cycle, numbered from 1.
+pub trait Observer {
+    fn during(&mut self, cycle: u64, regs: &Registers);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Breakpoint {
+    /// Stop before the given cycle.
+    Cycle(u64),
+    /// Stop before every cycle during which a register holds a value, unless
+    /// it already held it during the cycle before.
+    Value(usize, i64),
+    /// Stop before any cycle during which a register has changed.
+    Watch(usize),
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Breakpoint::Cycle(c) => write!(f, "cycle {}", c),
+            Breakpoint::Value(r, v) => write!(f, "{}={}", REGISTERS[*r], v),
+            Breakpoint::Watch(r) => write!(f, "watch {}", REGISTERS[*r]),
+        }
+    }
+}
+
+pub struct Cpu {
+    pub regs: Registers,
+    program: Vec<Box<dyn Instruction>>,
+    pc: usize,
+    /// Cycles already spent on the instruction at `pc`.
+    spent: u32,
+    /// Number of cycles completed.
+    pub cycle: u64,
+    /// Registers during the last completed cycle.
+    last: Option<Registers>,
+    /// Cycle `run` last stopped before, and the index of the breakpoint that
+    /// stopped it.
+    fired: Option<(u64, usize)>,
+}
+
+impl Cpu {
+    pub fn new(program: Vec<Box<dyn Instruction>>) -> Self {
+        Cpu {
+            regs: Registers::default(),
+            program,
+            pc: 0,
+            spent: 0,
+            cycle: 0,
+            last: None,
+            fired: None,
+        }
+    }
+
+    pub fn halted(&self) -> bool {
+        self.pc >= self.program.len()
+    }
+
+    /// Runs a single cycle.
+    pub fn tick(&mut self, observers: &mut [&mut dyn Observer]) {
+        let Some(ins) = self.program.get(self.pc) else {
+            return;
+        };
+        self.cycle += 1;
+        for o in observers.iter_mut() {
+            o.during(self.cycle, &self.regs);
+        }
+        self.last = Some(self.regs);
+        self.spent += 1;
+        if self.spent >= ins.cycles() {
+            ins.execute(&mut self.regs);
+            self.pc += 1;
+            self.spent = 0;
+        }
+    }
+
+    fn hit(&self, b: &Breakpoint) -> bool {
+        let next = self.cycle + 1;
+        match *b {
+            Breakpoint::Cycle(c) => c == next,
+            Breakpoint::Value(r, v) => {
+                self.regs.get(r) == v && self.last.is_none_or(|l| l.get(r) != v)
+            }
+            Breakpoint::Watch(r) => self.last.is_some_and(|l| l.get(r) != self.regs.get(r)),
+        }
+    }
+
+    /// Runs until the program ends or a breakpoint is hit. Returns the
+    /// breakpoint. Breakpoints that already stopped before the current cycle
+    /// are skipped, so that calling this again resumes.
+    pub fn run(
+        &mut self,
+        observers: &mut [&mut dyn Observer],
+        breakpoints: &[Breakpoint],
+    ) -> Option<Breakpoint> {
+        while !self.halted() {
+            let skip = match self.fired {
+                Some((c, i)) if c == self.cycle => i + 1,
+                _ => 0,
+            };
+            if let Some(i) = (skip..breakpoints.len()).find(|&i| self.hit(&breakpoints[i])) {
+                self.fired = Some((self.cycle, i));
+                return Some(breakpoints[i]);
+            }
+            self.tick(observers);
+        }
+        None
+    }
+}
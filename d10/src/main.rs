@@ -1,59 +1,118 @@
-use std::cmp::max;
-use std::collections::HashSet;
+mod cpu;
+
+use cpu::{Breakpoint, Cpu, Observer, Registers};
+use std::env;
 use std::io;
 
-#[derive(Debug)]
-enum Cmd {
-    Addx(i32),
-    Noop,
+/// Sums `cycle * x` during the 20th cycle and every 40 cycles after.
+#[derive(Default)]
+struct SignalStrength(i64);
+
+impl Observer for SignalStrength {
+    fn during(&mut self, cycle: u64, regs: &Registers) {
+        if cycle % 40 == 20 {
+            self.0 += cycle as i64 * regs.get(0);
+        }
+    }
+}
+
+/// Draws a pixel per cycle, lit when the 3 pixel wide sprite centered on
+/// `x` covers it.
+#[derive(Default)]
+struct Crt(Vec<Vec<bool>>);
+
+impl Observer for Crt {
+    fn during(&mut self, cycle: u64, regs: &Registers) {
+        let pos = ((cycle - 1) % 40) as i64;
+        if pos == 0 {
+            self.0.push(Vec::new());
+        }
+        let lit = (regs.get(0) - pos).abs() <= 1;
+        self.0.last_mut().unwrap().push(lit);
+    }
+}
+
+/// Prints the registers during every cycle.
+struct Trace;
+
+impl Observer for Trace {
+    fn during(&mut self, cycle: u64, regs: &Registers) {
+        println!("{:>4}: {}", cycle, regs);
+    }
 }
 
-fn parse_line(l: &str) -> Cmd {
-    if l == "noop" {
-        return Cmd::Noop;
+fn parse_breakpoint(flag: &str, value: &str) -> Result<Breakpoint, String> {
+    match flag {
+        "--break-cycle" => value
+            .parse()
+            .map(Breakpoint::Cycle)
+            .map_err(|_| format!("bad cycle {}", value)),
+        "--break" => {
+            let (r, v) = value
+                .split_once('=')
+                .ok_or_else(|| format!("expected R=V: {}", value))?;
+            let v = v.parse().map_err(|_| format!("bad value {}", v))?;
+            Ok(Breakpoint::Value(cpu::parse_register(r)?, v))
+        }
+        "--watch" => Ok(Breakpoint::Watch(cpu::parse_register(value)?)),
+        _ => Err(format!("unknown flag {}", flag)),
     }
-    assert!(l.starts_with("addx "));
-    let a = l[5..].parse::<i32>().unwrap();
-    Cmd::Addx(a)
 }
 
 fn main() -> io::Result<()> {
+    let usage = "usage: d10 [--trace] [--break-cycle N] [--break R=V] [--watch R]";
+    let mut trace = false;
+    let mut breakpoints = Vec::new();
+    let mut args = env::args().skip(1);
+    while let Some(a) = args.next() {
+        match a.as_str() {
+            "--trace" => trace = true,
+            _ => {
+                let value = args.next().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, usage.to_string())
+                })?;
+                let b = parse_breakpoint(&a, &value)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+                breakpoints.push(b);
+            }
+        }
+    }
+
     let stdin = io::stdin();
+    let program = stdin
+        .lines()
+        .map(|l| cpu::parse_line(&l?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)))
+        .collect::<io::Result<Vec<_>>>()?;
 
-    let mut x = 1;
-    let mut x_log = Vec::new();
-
-    for l in stdin.lines() {
-        let cmd = parse_line(&l.unwrap());
-        match cmd {
-            Cmd::Noop => x_log.push(x),
-            Cmd::Addx(a) => {
-                x_log.push(x);
-                x_log.push(x);
-                x += a;
-            }
+    let mut cpu = Cpu::new(program);
+    let mut signal = SignalStrength::default();
+    let mut crt = Crt::default();
+    let mut tracer = Trace;
+    loop {
+        let mut observers: Vec<&mut dyn Observer> = vec![&mut signal, &mut crt];
+        if trace {
+            observers.push(&mut tracer);
+        }
+        match cpu.run(&mut observers, &breakpoints) {
+            Some(b) => println!("break ({}) before cycle {}: {}", b, cpu.cycle + 1, cpu.regs),
+            None => break,
         }
     }
-    println!(
-        "signal strength: {:?}",
-        x_log
+
+    println!("signal strength: {}", signal.0);
+    let screen = &crt.0[..crt.0.len().min(ocr::HEIGHT)];
+    for row in screen {
+        let row = row
             .iter()
-            .enumerate()
-            .skip(19)
-            .step_by(40)
-            .map(|(c, x)| (c + 1) as i32 * x)
-            .sum::<i32>()
-    );
-    for y in 0..6 {
-        for x in 0..40 {
-            let xv = x_log[x + y * 40];
-            if (xv - x as i32).abs() <= 1 {
-                print!("#");
-            } else {
-                print!(".");
-            }
-        }
-        println!("");
+            .map(|&lit| if lit { '#' } else { '.' })
+            .collect::<String>();
+        println!("{}", row);
+    }
+    // Not every program draws letters, so a screen that cannot be read is
+    // only reported.
+    match ocr::decode(screen) {
+        Ok(letters) => println!("letters: {}", letters),
+        Err(e) => eprintln!("cannot read the screen: {}", e),
     }
     Ok(())
 }
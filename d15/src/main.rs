@@ -1,8 +1,4 @@
-use std::cmp::Ordering;
-use std::cmp::{max, min};
-use std::collections::{HashSet, VecDeque};
-use std::fmt;
-use std::fmt::Display;
+use intervals::IntervalSet;
 use std::io;
 use std::ops::RangeInclusive;
 
@@ -21,70 +17,54 @@ fn parse_line(mut l: &str) -> ((i64, i64), (i64, i64)) {
     ((sx, sy), (bx, by))
 }
 
-fn count_free(row: i64, sensors: &[((i64, i64), i64)], beacons: &[(i64, i64)]) -> usize {
-    let mut free = HashSet::new();
-    for (s, d) in sensors.iter() {
-        let d = d - (s.1 - row).abs();
-        if d >= 0 {
-            for x in s.0 - d..=s.0 + d {
-                free.insert(x);
-            }
-        }
-    }
+fn covered(row: i64, sensors: &[((i64, i64), i64)]) -> IntervalSet {
+    sensors
+        .iter()
+        .map(|(s, d)| {
+            let d = d - (s.1 - row).abs();
+            s.0 - d..=s.0 + d
+        })
+        .collect()
+}
+
+fn count_free(row: i64, sensors: &[((i64, i64), i64)], beacons: &[(i64, i64)]) -> u64 {
+    let mut free = covered(row, sensors);
     for b in beacons.iter() {
         if b.1 == row {
-            free.take(&b.0);
+            free.remove(b.0..=b.0);
         }
     }
     free.len()
 }
 
-fn empty_spot(row: i64, sensors: &[((i64, i64), i64)], r: RangeInclusive<i64>) -> Option<i64> {
-    let mut ranges = Vec::new();
-    ranges.push(r);
-
-    for (s, d) in sensors.iter() {
-        let d = d - (s.1 - row).abs();
-        if d >= 0 {
-            let punch = s.0 - d..=s.0 + d;
-            let mut nranges: Vec<RangeInclusive<i64>> = Vec::new();
-            for r in ranges {
-                let upper = *r.start()..=min(*r.end(), punch.start() - 1);
-                if !upper.is_empty() {
-                    nranges.push(upper);
-                }
-
-                let lower = max(punch.end() + 1, *r.start())..=*r.end();
-                if !lower.is_empty() {
-                    nranges.push(lower);
-                }
-            }
-            ranges = nranges;
-        }
-    }
-    if ranges.len() == 0 {
-        return None;
-    }
-    assert!(ranges.len() == 1);
-    assert_eq!(ranges[0].start(), ranges[0].end());
-    Some(*ranges[0].start())
+fn empty_spots(row: i64, sensors: &[((i64, i64), i64)], r: RangeInclusive<i64>) -> IntervalSet {
+    IntervalSet::from(r).difference(&covered(row, sensors))
 }
 
-fn find_empty(sensors: &[((i64, i64), i64)], r: RangeInclusive<i64>) -> (i64, i64) {
-    let mut e = Vec::new();
+/// The free cells of every row in `r`, as runs of consecutive rows that share
+/// the same gaps. Rows without gaps are left out.
+fn find_empty(
+    sensors: &[((i64, i64), i64)],
+    r: RangeInclusive<i64>,
+) -> Vec<(RangeInclusive<i64>, IntervalSet)> {
+    let mut e: Vec<(RangeInclusive<i64>, IntervalSet)> = Vec::new();
     for y in r.clone() {
-        if let Some(x) = empty_spot(y, sensors, r.clone()) {
-            e.push((x, y));
+        let gaps = empty_spots(y, sensors, r.clone());
+        if gaps.is_empty() {
+            continue;
+        }
+        match e.last_mut() {
+            Some((rows, g)) if *rows.end() == y - 1 && *g == gaps => *rows = *rows.start()..=y,
+            _ => e.push((y..=y, gaps)),
         }
     }
-    assert_eq!(1, e.len());
-    return e[0];
+    e
 }
 
 fn main() -> io::Result<()> {
     let stdin = io::stdin();
 
-    let mut lines = stdin.lines().map(|l| l.unwrap()).collect::<Vec<_>>();
+    let lines = stdin.lines().map(|l| l.unwrap()).collect::<Vec<_>>();
 
     let mut sensors = Vec::new();
     let mut beacons = Vec::new();
@@ -102,13 +82,26 @@ fn main() -> io::Result<()> {
     println!("row {}: {}", row, count_free(row, &sensors, &beacons));
 
     let r = 0..=4000000;
-    let e = find_empty(&sensors, r.clone());
-    println!(
-        "empty_spot in {:?}: {:?} freq: {}",
-        r,
-        e,
-        e.0 * 4000000 + e.1
-    );
+    let empty = find_empty(&sensors, r.clone());
+    for (rows, gaps) in empty.iter() {
+        println!(
+            "empty in {:?}: rows {:?} columns {:?}",
+            r,
+            rows,
+            gaps.ranges()
+        );
+    }
+    let free = empty
+        .iter()
+        .map(|(rows, gaps)| (rows.end() - rows.start() + 1) as u64 * gaps.len())
+        .sum::<u64>();
+    match empty.first() {
+        Some((rows, gaps)) if free == 1 => {
+            let (x, y) = (*gaps.ranges()[0].start(), *rows.start());
+            println!("empty_spot: {:?} freq: {}", (x, y), x * 4000000 + y);
+        }
+        _ => println!("{} free cells, no single empty spot", free),
+    }
 
     Ok(())
 }
@@ -0,0 +1,263 @@
+use std::cmp::{max, min};
+use std::ops::RangeInclusive;
+
+/// A set of integers stored as sorted, disjoint, non-adjacent inclusive ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<RangeInclusive<i64>>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ranges(&self) -> &[RangeInclusive<i64>] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers covered by the set.
+    pub fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|r| (r.end() - r.start()) as u64 + 1)
+            .sum()
+    }
+
+    pub fn insert(&mut self, r: RangeInclusive<i64>) {
+        if r.is_empty() {
+            return;
+        }
+        let (mut start, mut end) = (*r.start(), *r.end());
+        // Ranges that overlap or touch `r` get merged into it.
+        let first = self
+            .ranges
+            .partition_point(|x| x.end().saturating_add(1) < start);
+        let last = self
+            .ranges
+            .partition_point(|x| *x.start() <= end.saturating_add(1));
+        if first < last {
+            start = min(start, *self.ranges[first].start());
+            end = max(end, *self.ranges[last - 1].end());
+        }
+        self.ranges.splice(first..last, [start..=end]);
+    }
+
+    pub fn remove(&mut self, r: RangeInclusive<i64>) {
+        if r.is_empty() {
+            return;
+        }
+        let (start, end) = (*r.start(), *r.end());
+        let first = self.ranges.partition_point(|x| *x.end() < start);
+        let last = self.ranges.partition_point(|x| *x.start() <= end);
+        if first == last {
+            return;
+        }
+        let mut rest = Vec::new();
+        if *self.ranges[first].start() < start {
+            rest.push(*self.ranges[first].start()..=start - 1);
+        }
+        if *self.ranges[last - 1].end() > end {
+            rest.push(end + 1..=*self.ranges[last - 1].end());
+        }
+        self.ranges.splice(first..last, rest);
+    }
+
+    pub fn contains(&self, x: i64) -> bool {
+        self.contains_range(&(x..=x))
+    }
+
+    /// Whether every integer in `r` is in the set.
+    pub fn contains_range(&self, r: &RangeInclusive<i64>) -> bool {
+        if r.is_empty() {
+            return true;
+        }
+        let i = self.ranges.partition_point(|x| x.end() < r.start());
+        i < self.ranges.len()
+            && self.ranges[i].start() <= r.start()
+            && r.end() <= self.ranges[i].end()
+    }
+
+    pub fn is_superset(&self, other: &IntervalSet) -> bool {
+        other.ranges.iter().all(|r| self.contains_range(r))
+    }
+
+    pub fn overlaps(&self, other: &IntervalSet) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut r = self.clone();
+        for x in other.ranges.iter() {
+            r.insert(x.clone());
+        }
+        r
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut r = IntervalSet::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            let x = *max(a.start(), b.start())..=*min(a.end(), b.end());
+            if !x.is_empty() {
+                r.ranges.push(x);
+            }
+            if a.end() < b.end() {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        r
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut r = self.clone();
+        for x in other.ranges.iter() {
+            r.remove(x.clone());
+        }
+        r
+    }
+
+    /// All integers in the set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        self.ranges.iter().flat_map(|r| r.clone())
+    }
+}
+
+impl From<RangeInclusive<i64>> for IntervalSet {
+    fn from(r: RangeInclusive<i64>) -> Self {
+        let mut s = IntervalSet::new();
+        s.insert(r);
+        s
+    }
+}
+
+impl FromIterator<RangeInclusive<i64>> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<i64>>>(iter: I) -> Self {
+        let mut s = IntervalSet::new();
+        for r in iter {
+            s.insert(r);
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[RangeInclusive<i64>]) -> IntervalSet {
+        ranges.iter().cloned().collect()
+    }
+
+    #[test]
+    fn insert_merges_overlapping_and_adjacent() {
+        let mut s = set(&[0..=2, 10..=12]);
+        s.insert(3..=4);
+        assert_eq!(s.ranges(), &[0..=4, 10..=12]);
+        s.insert(6..=9);
+        assert_eq!(s.ranges(), &[0..=4, 6..=12]);
+        s.insert(5..=5);
+        assert_eq!(s.ranges(), &[0..=12]);
+    }
+
+    #[test]
+    fn insert_keeps_gaps_of_one() {
+        let s = set(&[0..=2, 4..=6]);
+        assert_eq!(s.ranges(), &[0..=2, 4..=6]);
+        assert!(!s.contains(3));
+        assert_eq!(s.len(), 6);
+    }
+
+    #[test]
+    fn insert_spanning_several() {
+        let s = set(&[0..=1, 5..=6, 9..=10, 20..=21]);
+        let mut t = s.clone();
+        t.insert(1..=9);
+        assert_eq!(t.ranges(), &[0..=10, 20..=21]);
+    }
+
+    #[test]
+    fn insert_empty_range_is_ignored() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let s = set(&[5..=4, 3..=3]);
+        assert_eq!(s.ranges(), &[3..=3]);
+    }
+
+    #[test]
+    fn insert_at_extremes() {
+        let mut s = set(&[i64::MIN..=i64::MIN + 1]);
+        s.insert(i64::MAX - 1..=i64::MAX);
+        s.insert(i64::MIN + 2..=0);
+        assert_eq!(s.ranges(), &[i64::MIN..=0, i64::MAX - 1..=i64::MAX]);
+    }
+
+    #[test]
+    fn remove_boundaries() {
+        let mut s = set(&[0..=10]);
+        s.remove(0..=0);
+        assert_eq!(s.ranges(), &[1..=10]);
+        s.remove(10..=10);
+        assert_eq!(s.ranges(), &[1..=9]);
+        s.remove(5..=5);
+        assert_eq!(s.ranges(), &[1..=4, 6..=9]);
+        s.remove(4..=6);
+        assert_eq!(s.ranges(), &[1..=3, 7..=9]);
+    }
+
+    #[test]
+    fn remove_at_extremes() {
+        let mut s = set(&[i64::MIN..=i64::MAX]);
+        s.remove(i64::MIN..=i64::MIN);
+        s.remove(i64::MAX..=i64::MAX);
+        assert_eq!(s.ranges(), &[i64::MIN + 1..=i64::MAX - 1]);
+        s.remove(i64::MIN..=i64::MAX);
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn remove_outside_or_between_is_noop() {
+        let mut s = set(&[0..=2, 6..=8]);
+        s.remove(3..=5);
+        s.remove(-5..=-1);
+        s.remove(9..=20);
+        assert_eq!(s.ranges(), &[0..=2, 6..=8]);
+    }
+
+    #[test]
+    fn remove_spanning_several() {
+        let mut s = set(&[0..=2, 4..=6, 8..=10]);
+        s.remove(1..=9);
+        assert_eq!(s.ranges(), &[0..=0, 10..=10]);
+        s.remove(-1..=11);
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn contains_range() {
+        let s = set(&[0..=2, 4..=6]);
+        assert!(s.contains_range(&(4..=6)));
+        assert!(!s.contains_range(&(2..=4)));
+        assert!(!s.contains(7));
+        assert!(s.is_superset(&set(&[0..=1, 5..=5])));
+        assert!(!s.is_superset(&set(&[0..=3])));
+    }
+
+    #[test]
+    fn set_operations() {
+        let a = set(&[0..=5, 10..=15]);
+        let b = set(&[3..=12]);
+        assert_eq!(a.union(&b).ranges(), &[0..=15]);
+        assert_eq!(a.intersection(&b).ranges(), &[3..=5, 10..=12]);
+        assert_eq!(a.difference(&b).ranges(), &[0..=2, 13..=15]);
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&set(&[6..=9])));
+        assert_eq!(set(&[1..=2, 5..=5]).iter().collect::<Vec<_>>(), [1, 2, 5]);
+    }
+}
@@ -0,0 +1,72 @@
+//! Reads the block letters some puzzles draw on a screen.
+//!
+//! Letters are 4 pixels wide and 6 high, with one blank column after each.
+
+pub const WIDTH: usize = 4;
+pub const HEIGHT: usize = 6;
+
+const FONT: [(char, &str); 17] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+fn glyph(rows: &[&[bool]], left: usize) -> String {
+    rows.iter()
+        .flat_map(|r| (left..left + WIDTH).map(|x| r.get(x).copied().unwrap_or(false)))
+        .map(|lit| if lit { '#' } else { '.' })
+        .collect()
+}
+
+/// Decodes a screen given as rows of lit pixels. The screen must be exactly
+/// `HEIGHT` rows; any glyph not in the font is an error that shows it.
+pub fn decode<R: AsRef<[bool]>>(screen: &[R]) -> Result<String, String> {
+    let rows = screen.iter().map(|r| r.as_ref()).collect::<Vec<_>>();
+    if rows.len() != HEIGHT {
+        return Err(format!("expected {} rows, got {}", HEIGHT, rows.len()));
+    }
+    let w = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let mut letters = String::new();
+    for left in (0..w).step_by(WIDTH + 1) {
+        let g = glyph(&rows, left);
+        match FONT.iter().find(|(_, f)| *f == g) {
+            Some((c, _)) => letters.push(*c),
+            None => {
+                let drawn = g
+                    .as_bytes()
+                    .chunks(WIDTH)
+                    .map(|r| String::from_utf8_lossy(r))
+                    .collect::<Vec<_>>();
+                return Err(format!(
+                    "unknown glyph at column {}:\n{}",
+                    left,
+                    drawn.join("\n")
+                ));
+            }
+        }
+    }
+    Ok(letters)
+}
+
+/// Like [`decode`], for a screen drawn with `#` for lit pixels.
+pub fn decode_str(screen: &str) -> Result<String, String> {
+    let rows = screen
+        .lines()
+        .map(|l| l.chars().map(|c| c == '#').collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    decode(&rows)
+}